@@ -0,0 +1,204 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{parse_quote, Data, DeriveInput, Error, Field, Fields, Ident, Index, Member, Result, Type};
+
+/// how field is compared
+enum Mode {
+	Diff,
+	Replace,
+}
+
+struct DiffField {
+	/// member in original type
+	member: Member,
+	/// member in generated change type (skipped fields shift tuple index)
+	change_member: Member,
+	ty: Type,
+	mode: Mode,
+}
+
+impl DiffField {
+	fn change_type(&self) -> TokenStream {
+		let ty = &self.ty;
+		match self.mode {
+			Mode::Diff => quote!(<#ty as ::pedestal_rs::ext::Diff>::Change),
+			Mode::Replace => quote!(::pedestal_rs::ext::Replace<#ty>),
+		}
+	}
+
+	fn diff(&self, old: &TokenStream, new: &TokenStream) -> TokenStream {
+		match self.mode {
+			Mode::Diff => quote!(::pedestal_rs::ext::Diff::diff(#old, #new)),
+			Mode::Replace => quote!(::pedestal_rs::ext::Replace::compare(#old, #new)),
+		}
+	}
+}
+
+/// read `#[diff(...)]` attribute; return None if field is skipped
+fn mode(field: &Field) -> Result<Option<Mode>> {
+	let mut mode = Some(Mode::Diff);
+	for attr in field.attrs.iter().filter(|it| it.path().is_ident("diff")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("skip") {
+				mode = None;
+			} else if meta.path.is_ident("replace") {
+				mode = Some(Mode::Replace);
+			} else {
+				return Err(meta.error("expected `skip` or `replace`"));
+			}
+			Ok(())
+		})?;
+	}
+	Ok(mode)
+}
+
+fn diff_fields(fields: &Fields) -> Result<Vec<DiffField>> {
+	let mut out = Vec::new();
+	for (i, field) in fields.iter().enumerate() {
+		let Some(mode) = mode(field)? else { continue; };
+		let (member, change_member) = match &field.ident {
+			Some(ident) => (Member::Named(ident.clone()), Member::Named(ident.clone())),
+			None => (Member::Unnamed(Index::from(i)), Member::Unnamed(Index::from(out.len()))),
+		};
+		out.push(DiffField { member, change_member, ty: field.ty.clone(), mode });
+	}
+	Ok(out)
+}
+
+/// body of change struct or variant (enum variant fields can't have visibility)
+fn change_body(fields: &Fields, diff: &[DiffField], public: bool) -> TokenStream {
+	let types = diff.iter().map(DiffField::change_type);
+	let vis = if public { quote!(pub) } else { quote!() };
+	match fields {
+		Fields::Named(_) => {
+			let members = diff.iter().map(|it| &it.change_member);
+			quote!({ #(#vis #members: ::core::option::Option<#types>,)* })
+		}
+		_ => quote!((#(#vis ::core::option::Option<#types>,)*)),
+	}
+}
+
+/// create `Some(#change { .. })` if any field was changed
+fn construct(path: TokenStream, diff: &[DiffField], old: &[TokenStream], new: &[TokenStream]) -> TokenStream {
+	let bindings = (0..diff.len()).map(|i| format_ident!("__change_{}", i)).collect::<Vec<_>>();
+	let exprs = diff.iter().zip(old.iter().zip(new)).map(|(field, (old, new))| field.diff(old, new));
+	let members = diff.iter().map(|it| &it.change_member);
+	quote! {{
+		#(let #bindings = #exprs;)*
+		if #(#bindings.is_none())&&* {
+			::core::option::Option::None
+		} else {
+			::core::option::Option::Some(#path { #(#members: #bindings,)* })
+		}
+	}}
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+	let name = &input.ident;
+	let vis = &input.vis;
+	let change = format_ident!("{}Change", name);
+	let doc = format!("Change set of [`{}`] generated by `#[derive(Diff)]`", name);
+	let mut generics = input.generics.clone();
+
+	let all_fields = match &input.data {
+		Data::Struct(data) => vec![&data.fields],
+		Data::Enum(data) => data.variants.iter().map(|it| &it.fields).collect(),
+		Data::Union(_) => return Err(Error::new_spanned(&input, "Diff can't be derived for union")),
+	};
+	let where_clause = generics.make_where_clause();
+	for fields in all_fields {
+		for field in diff_fields(fields)? {
+			let ty = &field.ty;
+			where_clause.predicates.push(match field.mode {
+				Mode::Diff => parse_quote!(#ty: ::pedestal_rs::ext::Diff),
+				Mode::Replace => parse_quote!(#ty: ::core::clone::Clone + ::core::fmt::Debug + ::core::cmp::PartialEq),
+			});
+		}
+	}
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	let params = &generics.params;
+
+	match &input.data {
+		Data::Struct(data) => {
+			let diff = diff_fields(&data.fields)?;
+			if diff.is_empty() {
+				return Ok(quote! {
+					impl #impl_generics ::pedestal_rs::ext::Diff for #name #ty_generics #where_clause {
+						type Change = ();
+
+						fn diff(&self, _new: &Self) -> ::core::option::Option<Self::Change> {
+							::core::option::Option::None
+						}
+					}
+				});
+			}
+			let body = change_body(&data.fields, &diff, true);
+			let definition = match data.fields {
+				Fields::Named(_) => quote!(#vis struct #change<#params> #where_clause #body),
+				_ => quote!(#vis struct #change<#params> #body #where_clause;),
+			};
+			let old = diff.iter().map(|it| { let member = &it.member; quote!(&self.#member) }).collect::<Vec<_>>();
+			let new = diff.iter().map(|it| { let member = &it.member; quote!(&new.#member) }).collect::<Vec<_>>();
+			let construct = construct(quote!(#change), &diff, &old, &new);
+			Ok(quote! {
+				#[doc = #doc]
+				#[derive(Debug, Clone, PartialEq)]
+				#definition
+
+				impl #impl_generics ::pedestal_rs::ext::Diff for #name #ty_generics #where_clause {
+					type Change = #change #ty_generics;
+
+					fn diff(&self, new: &Self) -> ::core::option::Option<Self::Change> {
+						#construct
+					}
+				}
+			})
+		}
+		Data::Enum(data) => {
+			let mut variants = Vec::new();
+			let mut arms = Vec::new();
+			for variant in &data.variants {
+				let ident: &Ident = &variant.ident;
+				let diff = diff_fields(&variant.fields)?;
+				if diff.is_empty() {
+					arms.push(quote!((Self::#ident { .. }, Self::#ident { .. }) => ::core::option::Option::None,));
+					continue;
+				}
+				let body = change_body(&variant.fields, &diff, false);
+				variants.push(quote!(#ident #body));
+				let members = diff.iter().map(|it| &it.member).collect::<Vec<_>>();
+				let old = (0..diff.len()).map(|i| { let it = format_ident!("__old_{}", i); quote!(#it) }).collect::<Vec<_>>();
+				let new = (0..diff.len()).map(|i| { let it = format_ident!("__new_{}", i); quote!(#it) }).collect::<Vec<_>>();
+				let construct = construct(quote!(#change::#ident), &diff, &old, &new);
+				arms.push(quote! {
+					(Self::#ident { #(#members: #old,)* .. }, Self::#ident { #(#members: #new,)* .. }) => #construct,
+				});
+			}
+			Ok(quote! {
+				#[doc = #doc]
+				#[derive(Debug, Clone, PartialEq)]
+				#vis enum #change<#params> #where_clause {
+					#(#variants,)*
+					/// value was changed to another variant
+					Replaced(::pedestal_rs::ext::Replace<#name #ty_generics>),
+				}
+
+				impl #impl_generics ::pedestal_rs::ext::Diff for #name #ty_generics #where_clause {
+					type Change = #change #ty_generics;
+
+					#[allow(unreachable_patterns)]
+					fn diff(&self, new: &Self) -> ::core::option::Option<Self::Change> {
+						match (self, new) {
+							#(#arms)*
+							_ => ::core::option::Option::Some(#change::Replaced(::pedestal_rs::ext::Replace {
+								old: ::core::clone::Clone::clone(self),
+								new: ::core::clone::Clone::clone(new),
+							})),
+						}
+					}
+				}
+			})
+		}
+		Data::Union(_) => unreachable!(),
+	}
+}
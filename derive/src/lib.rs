@@ -0,0 +1,30 @@
+//! Derive macros of `pedestal-rs`; use them through `pedestal_rs::ext` with `derive` feature
+
+use proc_macro::TokenStream;
+use syn::{parse_macro_input, DeriveInput};
+
+mod diff;
+mod modify;
+
+/// Derive `pedestal_rs::ext::Diff` and generate `<Name>Change` type with same visibility
+/// + struct: change struct has `Option<Field::Change>` for every field
+/// + enum: change enum has same variants with `Option<Field::Change>` fields and `Replaced` variant for variant change
+/// # Attributes
+/// + `#[diff(skip)]` ignore field
+/// + `#[diff(replace)]` compare field with `PartialEq` and record it as `Replace<Field>` (field doesn't need to implement `Diff`)
+#[proc_macro_derive(Diff, attributes(diff))]
+pub fn derive_diff(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	diff::expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
+
+/// Derive `with_<field>(value)`, `modify_<field>(|it| ..)` and `modify_at(lens!(a.b), |it| ..)` that return modified clone (see `CloneExt::as_owned`)
+/// + same methods are generated in `<Name>ArcExt` trait for `Arc<Name>`; they return new `Arc` (see `ArcExt::as_owned_arc`)
+/// + field named `at` is rejected because `modify_at` is reserved for lens
+/// # Attributes
+/// + `#[modify(skip)]` don't generate methods for field
+#[proc_macro_derive(Modify, attributes(modify))]
+pub fn derive_modify(input: TokenStream) -> TokenStream {
+	let input = parse_macro_input!(input as DeriveInput);
+	modify::expand(input).unwrap_or_else(syn::Error::into_compile_error).into()
+}
@@ -0,0 +1,117 @@
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Error, Fields, Result};
+
+/// check `#[modify(skip)]` attribute
+fn skipped(field: &syn::Field) -> Result<bool> {
+	let mut skip = false;
+	for attr in field.attrs.iter().filter(|it| it.path().is_ident("modify")) {
+		attr.parse_nested_meta(|meta| {
+			if meta.path.is_ident("skip") {
+				skip = true;
+				Ok(())
+			} else {
+				Err(meta.error("expected `skip`"))
+			}
+		})?;
+	}
+	Ok(skip)
+}
+
+pub fn expand(input: DeriveInput) -> Result<TokenStream> {
+	let name = &input.ident;
+	let vis = &input.vis;
+	let arc_ext = format_ident!("{}ArcExt", name);
+	let fields = match &input.data {
+		Data::Struct(data) => match &data.fields {
+			Fields::Named(fields) => &fields.named,
+			_ => return Err(Error::new_spanned(&input, "Modify can only be derived for struct with named fields")),
+		},
+		_ => return Err(Error::new_spanned(&input, "Modify can only be derived for struct")),
+	};
+
+	let mut generics = input.generics.clone();
+	let (_, ty_generics, _) = input.generics.split_for_impl();
+	generics.make_where_clause().predicates.push(syn::parse_quote!(#name #ty_generics: ::core::clone::Clone + 'static));
+	let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+	let params = &generics.params;
+
+	let mut methods = Vec::new();
+	let mut signatures = Vec::new();
+	let mut arc_methods = Vec::new();
+	for field in fields {
+		if skipped(field)? {
+			continue;
+		}
+		let ident = field.ident.as_ref().unwrap();
+		if ident == "at" {
+			return Err(Error::new_spanned(ident, "field `at` would generate `modify_at` that is reserved for lens; rename it or use `#[modify(skip)]`"));
+		}
+		let ty = &field.ty;
+		let with = format_ident!("with_{}", ident);
+		let modify = format_ident!("modify_{}", ident);
+		let with_doc = format!("Clone and replace `{}`", ident);
+		let modify_doc = format!("Clone and modify `{}`", ident);
+		methods.push(quote! {
+			#[doc = #with_doc]
+			#[must_use]
+			pub fn #with(&self, value: #ty) -> Self {
+				::pedestal_rs::ext::CloneExt::as_owned(self, |it| it.#ident = value)
+			}
+
+			#[doc = #modify_doc]
+			#[must_use]
+			pub fn #modify(&self, f: impl ::core::ops::FnOnce(&mut #ty)) -> Self {
+				::pedestal_rs::ext::CloneExt::as_owned(self, |it| f(&mut it.#ident))
+			}
+		});
+		signatures.push(quote! {
+			#[doc = #with_doc]
+			#[must_use]
+			fn #with(&self, value: #ty) -> ::std::sync::Arc<#name #ty_generics>;
+
+			#[doc = #modify_doc]
+			#[must_use]
+			fn #modify(&self, f: impl ::core::ops::FnOnce(&mut #ty)) -> ::std::sync::Arc<#name #ty_generics>;
+		});
+		arc_methods.push(quote! {
+			fn #with(&self, value: #ty) -> ::std::sync::Arc<#name #ty_generics> {
+				::pedestal_rs::ext::ArcExt::as_owned_arc(self, |it| it.#ident = value)
+			}
+
+			fn #modify(&self, f: impl ::core::ops::FnOnce(&mut #ty)) -> ::std::sync::Arc<#name #ty_generics> {
+				::pedestal_rs::ext::ArcExt::as_owned_arc(self, |it| f(&mut it.#ident))
+			}
+		});
+	}
+	let arc_doc = format!("`Arc<{}>` version of methods generated by `#[derive(Modify)]`", name);
+
+	Ok(quote! {
+		impl #impl_generics #name #ty_generics #where_clause {
+			#(#methods)*
+
+			/// Clone and modify nested field selected by `lens!(a.b.c)`
+			#[must_use]
+			pub fn modify_at<__T: ?Sized>(&self, lens: impl for<'a> ::core::ops::FnOnce(&'a mut Self) -> &'a mut __T, f: impl ::core::ops::FnOnce(&mut __T)) -> Self {
+				::pedestal_rs::ext::CloneExt::as_owned(self, |it| f(lens(it)))
+			}
+		}
+
+		#[doc = #arc_doc]
+		#vis trait #arc_ext<#params> #where_clause {
+			#(#signatures)*
+
+			/// Clone and modify nested field selected by `lens!(a.b.c)`
+			#[must_use]
+			fn modify_at<__T: ?Sized>(&self, lens: impl for<'a> ::core::ops::FnOnce(&'a mut #name #ty_generics) -> &'a mut __T, f: impl ::core::ops::FnOnce(&mut __T)) -> ::std::sync::Arc<#name #ty_generics>;
+		}
+
+		impl #impl_generics #arc_ext #ty_generics for ::std::sync::Arc<#name #ty_generics> #where_clause {
+			#(#arc_methods)*
+
+			fn modify_at<__T: ?Sized>(&self, lens: impl for<'a> ::core::ops::FnOnce(&'a mut #name #ty_generics) -> &'a mut __T, f: impl ::core::ops::FnOnce(&mut __T)) -> ::std::sync::Arc<#name #ty_generics> {
+				::pedestal_rs::ext::ArcExt::as_owned_arc(self, |it| f(lens(it)))
+			}
+		}
+	})
+}
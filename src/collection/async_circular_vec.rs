@@ -0,0 +1,210 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+use futures::{Sink, Stream};
+
+use crate::collection::CircularVec;
+
+/// Error returned when pushing into closed [AsyncCircularVec]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+/// Shared [CircularVec] that can be awaited until item is available  
+/// cloning this struct will create another handle to same buffer  
+/// this struct doesn't depend on any runtime so it can be used with any executor
+/// # Example
+/// ```rust
+/// use futures::executor::block_on;
+/// use pedestal_rs::collection::AsyncCircularVec;
+/// let vec = AsyncCircularVec::new(2);
+/// vec.push(1).unwrap();
+/// vec.push(2).unwrap();
+/// // oldest element will be discarded
+/// assert_eq!(vec.push(3), Ok(Some(1)));
+/// vec.close();
+/// assert_eq!(block_on(vec.pop()), Some(2));
+/// assert_eq!(block_on(vec.pop()), Some(3));
+/// // closed and empty
+/// assert_eq!(block_on(vec.pop()), None);
+/// ```
+pub struct AsyncCircularVec<T> {
+	inner: Arc<Mutex<Inner<T>>>,
+}
+
+struct Inner<T> {
+	vec: CircularVec<T>,
+	closed: bool,
+	wakers: Vec<Waker>,
+}
+
+impl<T> Inner<T> {
+	fn wake_all(&mut self) {
+		for waker in self.wakers.drain(..) {
+			waker.wake();
+		}
+	}
+
+	fn poll_pop(&mut self, cx: &mut Context<'_>) -> Poll<Option<T>> {
+		if let Some(item) = self.vec.pop() {
+			return Poll::Ready(Some(item));
+		}
+		if self.closed {
+			return Poll::Ready(None);
+		}
+		if !self.wakers.iter().any(|it| it.will_wake(cx.waker())) {
+			self.wakers.push(cx.waker().clone());
+		}
+		Poll::Pending
+	}
+}
+
+impl<T> AsyncCircularVec<T> {
+	/// Create new async circular vec with given size
+	pub fn new(size: usize) -> Self {
+		Self {
+			inner: Arc::new(Mutex::new(Inner {
+				vec: CircularVec::new(size),
+				closed: false,
+				wakers: Vec::new(),
+			}))
+		}
+	}
+
+	#[inline]
+	fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+		// nothing can panic while holding the lock; recover from poison anyway
+		self.inner.lock().unwrap_or_else(|it| it.into_inner())
+	}
+
+	/// Append value to ends of vec and wake pending consumers  
+	/// if vec is full it will return oldest element; return `Err(Closed)` if vec was closed
+	pub fn push(&self, item: T) -> Result<Option<T>, Closed> {
+		let mut inner = self.lock();
+		if inner.closed {
+			return Err(Closed);
+		}
+		let last = inner.vec.push(item);
+		inner.wake_all();
+		Ok(last)
+	}
+
+	/// Remove oldest element from vec without waiting
+	pub fn try_pop(&self) -> Option<T> {
+		self.lock().vec.pop()
+	}
+
+	/// Wait until oldest element is available and remove it from vec  
+	/// return None if vec was closed and all remaining elements were taken
+	pub fn pop(&self) -> Pop<'_, T> {
+		Pop(self)
+	}
+
+	/// Close this vec; pending consumers will drain remaining elements and then receive None
+	pub fn close(&self) {
+		let mut inner = self.lock();
+		inner.closed = true;
+		inner.wake_all();
+	}
+
+	/// Check if this vec was closed
+	pub fn is_closed(&self) -> bool {
+		self.lock().closed
+	}
+
+	/// Get length of this vec
+	pub fn len(&self) -> usize {
+		self.lock().vec.len()
+	}
+
+	/// Check if this vec is empty
+	pub fn is_empty(&self) -> bool {
+		self.lock().vec.is_empty()
+	}
+
+	/// Take all data from this vec without waiting
+	pub fn take(&self) -> Vec<T> {
+		self.lock().vec.take()
+	}
+}
+
+impl<T> Clone for AsyncCircularVec<T> {
+	fn clone(&self) -> Self {
+		Self { inner: Arc::clone(&self.inner) }
+	}
+}
+
+/// Future returned from [AsyncCircularVec::pop]
+pub struct Pop<'a, T>(&'a AsyncCircularVec<T>);
+
+impl<'a, T> Future for Pop<'a, T> {
+	type Output = Option<T>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		self.0.lock().poll_pop(cx)
+	}
+}
+
+impl<T> Stream for AsyncCircularVec<T> {
+	type Item = T;
+
+	fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+		self.lock().poll_pop(cx)
+	}
+}
+
+impl<T> Sink<T> for AsyncCircularVec<T> {
+	type Error = Closed;
+
+	fn poll_ready(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		// push never wait; oldest element will be discarded instead
+		if self.is_closed() {
+			Poll::Ready(Err(Closed))
+		} else {
+			Poll::Ready(Ok(()))
+		}
+	}
+
+	fn start_send(self: Pin<&mut Self>, item: T) -> Result<(), Self::Error> {
+		self.push(item).map(|_| ())
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+		self.close();
+		Poll::Ready(Ok(()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::thread;
+
+	use futures::executor::block_on;
+	use futures::{SinkExt, StreamExt};
+
+	use crate::collection::AsyncCircularVec;
+
+	#[test]
+	fn test_async_circular_vec() {
+		let vec = AsyncCircularVec::new(4);
+		let mut producer = vec.clone();
+		let handle = thread::spawn(move || {
+			block_on(async {
+				for i in 0..3 {
+					producer.send(i).await.unwrap();
+				}
+				SinkExt::close(&mut producer).await.unwrap();
+			})
+		});
+		let received = block_on(vec.clone().collect::<Vec<i32>>());
+		handle.join().unwrap();
+		assert_eq!(received, vec![0, 1, 2]);
+		assert!(vec.push(3).is_err());
+		assert_eq!(block_on(vec.pop()), None);
+	}
+}
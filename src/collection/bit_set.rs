@@ -0,0 +1,291 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::{BitAnd, BitOr, BitXor, Range, Sub};
+
+const BITS: usize = u64::BITS as usize;
+
+/// Set of small integers stored as bits; grows automatically on insertion
+/// + set operations work on whole words and can be auto-vectorized
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::{BitSet, Grid};
+/// // flood fill from top-left corner
+/// let grid = Grid::from_vec(3, 2, vec![0, 0, 1, 1, 0, 0]).unwrap();
+/// let mut visited = BitSet::with_capacity(grid.width() * grid.height());
+/// let mut stack = vec![(0, 0)];
+/// while let Some((x, y)) = stack.pop() {
+///     if grid[(x, y)] != 0 || !visited.insert(y * grid.width() + x) { continue; }
+///     stack.extend(grid.neighbors4(x, y));
+/// }
+/// assert_eq!(visited.iter().collect::<Vec<_>>(), vec![0, 1, 4, 5]);
+/// ```
+#[derive(Clone, Default)]
+pub struct BitSet {
+	words: Vec<u64>,
+}
+
+/// mask of bits in `range` inside single word
+#[inline]
+fn mask(start: usize, end: usize) -> u64 {
+	let high = if end == BITS { u64::MAX } else { (1 << end) - 1 };
+	high & !((1u64 << start) - 1)
+}
+
+impl BitSet {
+	/// Create new empty set
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create new empty set that can hold `0..bits` without reallocation
+	pub fn with_capacity(bits: usize) -> Self {
+		Self { words: vec![0; bits.div_ceil(BITS)] }
+	}
+
+	/// Create set from words returned by [BitSet::as_words]
+	pub fn from_words(words: Vec<u64>) -> Self {
+		Self { words }
+	}
+
+	/// Get number of bits that can be stored without reallocation
+	pub fn capacity(&self) -> usize {
+		self.words.len() * BITS
+	}
+
+	/// Get underlying words; bit `i` is stored in `words[i / 64] >> (i % 64)`
+	pub fn as_words(&self) -> &[u64] {
+		&self.words
+	}
+
+	fn grow(&mut self, bits: usize) {
+		let words = bits.div_ceil(BITS);
+		if words > self.words.len() {
+			self.words.resize(words, 0);
+		}
+	}
+
+	/// Check if `bit` is in this set
+	#[inline]
+	pub fn contains(&self, bit: usize) -> bool {
+		self.words.get(bit / BITS).is_some_and(|it| it & (1 << (bit % BITS)) != 0)
+	}
+
+	/// Add `bit` into this set; return false if it's already in this set
+	#[inline]
+	pub fn insert(&mut self, bit: usize) -> bool {
+		self.grow(bit + 1);
+		let word = &mut self.words[bit / BITS];
+		let old = *word;
+		*word |= 1 << (bit % BITS);
+		old != *word
+	}
+
+	/// Remove `bit` from this set; return false if it's not in this set
+	#[inline]
+	pub fn remove(&mut self, bit: usize) -> bool {
+		match self.words.get_mut(bit / BITS) {
+			Some(word) => {
+				let old = *word;
+				*word &= !(1 << (bit % BITS));
+				old != *word
+			}
+			None => false,
+		}
+	}
+
+	/// Flip `bit`; return true if it's in this set after flipping
+	#[inline]
+	pub fn toggle(&mut self, bit: usize) -> bool {
+		self.grow(bit + 1);
+		let word = &mut self.words[bit / BITS];
+		*word ^= 1 << (bit % BITS);
+		*word & (1 << (bit % BITS)) != 0
+	}
+
+	/// Call `f(word, mask)` for every word that intersect with `range`
+	fn for_range(&mut self, range: Range<usize>, mut f: impl FnMut(&mut u64, u64)) {
+		if range.is_empty() {
+			return;
+		}
+		let (first, last) = (range.start / BITS, (range.end - 1) / BITS);
+		for (i, word) in self.words[first..=last].iter_mut().enumerate() {
+			let i = first + i;
+			let start = if i == first { range.start % BITS } else { 0 };
+			let end = if i == last { (range.end - 1) % BITS + 1 } else { BITS };
+			f(word, mask(start, end));
+		}
+	}
+
+	/// Add every bit in `range`
+	pub fn insert_range(&mut self, range: Range<usize>) {
+		self.grow(range.end);
+		self.for_range(range, |word, mask| *word |= mask);
+	}
+
+	/// Remove every bit in `range`
+	pub fn remove_range(&mut self, range: Range<usize>) {
+		let range = range.start..range.end.min(self.capacity());
+		self.for_range(range, |word, mask| *word &= !mask);
+	}
+
+	/// Flip every bit in `range`
+	pub fn toggle_range(&mut self, range: Range<usize>) {
+		self.grow(range.end);
+		self.for_range(range, |word, mask| *word ^= mask);
+	}
+
+	/// Count bits of this set in `range`
+	pub fn count_range(&self, range: Range<usize>) -> usize {
+		let range = range.start..range.end.min(self.capacity());
+		if range.is_empty() {
+			return 0;
+		}
+		let (first, last) = (range.start / BITS, (range.end - 1) / BITS);
+		self.words[first..=last].iter().enumerate().map(|(i, word)| {
+			let i = first + i;
+			let start = if i == first { range.start % BITS } else { 0 };
+			let end = if i == last { (range.end - 1) % BITS + 1 } else { BITS };
+			(word & mask(start, end)).count_ones() as usize
+		}).sum()
+	}
+
+	/// Count bits in this set (popcount)
+	pub fn len(&self) -> usize {
+		self.words.iter().map(|it| it.count_ones() as usize).sum()
+	}
+
+	/// Check if there is no bit in this set
+	pub fn is_empty(&self) -> bool {
+		self.words.iter().all(|it| *it == 0)
+	}
+
+	/// Remove every bit without deallocating
+	pub fn clear(&mut self) {
+		self.words.fill(0);
+	}
+
+	/// Iterate bits in ascending order
+	pub fn iter(&self) -> impl Iterator<Item=usize> + '_ {
+		self.words.iter().enumerate().flat_map(|(i, word)| {
+			let mut word = *word;
+			std::iter::from_fn(move || {
+				if word == 0 { return None; }
+				let bit = word.trailing_zeros() as usize;
+				word &= word - 1;
+				Some(i * BITS + bit)
+			})
+		})
+	}
+
+	/// Add every bit of `other` into this set
+	pub fn union_with(&mut self, other: &BitSet) {
+		self.grow(other.capacity());
+		for (a, b) in self.words.iter_mut().zip(&other.words) { *a |= b; }
+	}
+
+	/// Keep only bits that are also in `other`
+	pub fn intersect_with(&mut self, other: &BitSet) {
+		for (a, b) in self.words.iter_mut().zip(other.words.iter().chain(std::iter::repeat(&0))) { *a &= b; }
+	}
+
+	/// Remove every bit of `other` from this set
+	pub fn difference_with(&mut self, other: &BitSet) {
+		for (a, b) in self.words.iter_mut().zip(&other.words) { *a &= !b; }
+	}
+
+	/// Keep bits that are in exactly one of both sets
+	pub fn symmetric_difference_with(&mut self, other: &BitSet) {
+		self.grow(other.capacity());
+		for (a, b) in self.words.iter_mut().zip(&other.words) { *a ^= b; }
+	}
+
+	/// Check if every bit of this set is in `other`
+	pub fn is_subset(&self, other: &BitSet) -> bool {
+		self.words.iter().enumerate().all(|(i, a)| a & !other.words.get(i).copied().unwrap_or(0) == 0)
+	}
+
+	/// Check if both sets have no common bit
+	pub fn is_disjoint(&self, other: &BitSet) -> bool {
+		self.words.iter().zip(&other.words).all(|(a, b)| a & b == 0)
+	}
+}
+
+impl PartialEq for BitSet {
+	fn eq(&self, other: &Self) -> bool {
+		let (short, long) = if self.words.len() <= other.words.len() { (self, other) } else { (other, self) };
+		short.words == long.words[..short.words.len()] && long.words[short.words.len()..].iter().all(|it| *it == 0)
+	}
+}
+
+impl Eq for BitSet {}
+
+impl Debug for BitSet {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_set().entries(self.iter()).finish()
+	}
+}
+
+impl Extend<usize> for BitSet {
+	fn extend<I: IntoIterator<Item=usize>>(&mut self, iter: I) {
+		for it in iter { self.insert(it); }
+	}
+}
+
+impl FromIterator<usize> for BitSet {
+	fn from_iter<I: IntoIterator<Item=usize>>(iter: I) -> Self {
+		let mut set = Self::new();
+		set.extend(iter);
+		set
+	}
+}
+
+macro_rules! impl_op {
+    ($op:ident, $fn:ident, $with:ident) => {
+		impl $op<&BitSet> for &BitSet {
+			type Output = BitSet;
+
+			fn $fn(self, rhs: &BitSet) -> Self::Output {
+				let mut set = self.clone();
+				set.$with(rhs);
+				set
+			}
+		}
+    };
+}
+
+impl_op!(BitOr, bitor, union_with);
+impl_op!(BitAnd, bitand, intersect_with);
+impl_op!(BitXor, bitxor, symmetric_difference_with);
+impl_op!(Sub, sub, difference_with);
+
+#[cfg(test)]
+mod test {
+	use crate::collection::BitSet;
+
+	#[test]
+	fn test_bit_set() {
+		let mut set = BitSet::new();
+		assert!(set.insert(3));
+		assert!(!set.insert(3));
+		assert!(set.toggle(200));
+		assert!(!set.toggle(3));
+		assert_eq!(set.iter().collect::<Vec<_>>(), vec![200]);
+		set.insert_range(60..130);
+		assert_eq!(set.len(), 71);
+		assert_eq!(set.count_range(0..64), 4);
+		set.remove_range(62..1000);
+		assert_eq!(set.iter().collect::<Vec<_>>(), vec![60, 61]);
+		set.toggle_range(61..63);
+		assert_eq!(set.iter().collect::<Vec<_>>(), vec![60, 62]);
+
+		let a = (0..10).collect::<BitSet>();
+		let b = (5..100).step_by(5).collect::<BitSet>();
+		assert_eq!((&a & &b).iter().collect::<Vec<_>>(), vec![5]);
+		assert_eq!((&a - &b).len(), 9);
+		assert_eq!((&a | &b).len(), 28);
+		assert_eq!((&a ^ &b).len(), 27);
+		assert!((&a & &b).is_subset(&a));
+		assert!(!a.is_disjoint(&b));
+		// trailing empty words are ignored
+		assert_eq!(BitSet::with_capacity(1000), BitSet::new());
+	}
+}
@@ -0,0 +1,511 @@
+use std::collections::TryReserveError;
+use std::io;
+use std::io::{BufRead, ErrorKind, Read, Write};
+#[cfg(any(feature = "async", feature = "tokio"))]
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::task::{Context, Poll, Waker};
+
+/// Circular buffer specialized for bytes  
+/// [Write] will only write as much as free space and return `ErrorKind::WouldBlock` when full,  
+/// use [ByteRing::write_overwrite] to discard oldest bytes instead
+/// # Example
+/// ```rust
+/// use std::io::{Read, Write};
+/// use pedestal_rs::collection::ByteRing;
+/// let mut ring = ByteRing::new(8);
+/// ring.write_all(b"Hello").unwrap();
+/// assert_eq!(ring.write_overwrite(b" world"), 3);
+/// let mut out = String::new();
+/// ring.read_to_string(&mut out).unwrap();
+/// assert_eq!(out, "lo world");
+/// ```
+pub struct ByteRing {
+	/// allocated buffer; may be shorter than `capacity` for [ByteRing::lazy]
+	buf: Box<[u8]>,
+	/// index of oldest byte
+	head: usize,
+	len: usize,
+	capacity: usize,
+}
+
+impl ByteRing {
+	/// Create new byte ring with given size
+	pub fn new(capacity: usize) -> Self {
+		Self {
+			buf: vec![0; capacity].into_boxed_slice(),
+			head: 0,
+			len: 0,
+			capacity,
+		}
+	}
+
+	/// Same as [ByteRing::new] but buffer is allocated as bytes are written;
+	/// useful when capacity come from untrusted input
+	pub fn lazy(capacity: usize) -> Self {
+		Self { buf: Box::new([]), head: 0, len: 0, capacity }
+	}
+
+	/// Same as [ByteRing::new] but return error instead of abort when allocation failed
+	pub fn try_new(capacity: usize) -> Result<Self, TryReserveError> {
+		let mut buf = Vec::new();
+		buf.try_reserve_exact(capacity)?;
+		buf.resize(capacity, 0);
+		Ok(Self { buf: buf.into_boxed_slice(), head: 0, len: 0, capacity })
+	}
+
+	/// Create pipe that share single byte ring between writer and reader  
+	/// clone reader to fan out; every clone receive all bytes after position of reader it was cloned from,
+	/// and writer wait for the slowest reader when ring is full
+	pub fn pipe(capacity: usize) -> (ByteRingWriter, ByteRingReader) {
+		let shared = Arc::new(Mutex::new(Shared {
+			ring: ByteRing::new(capacity),
+			base: 0,
+			readers: vec![Some(Cursor { pos: 0, waker: None })],
+			write_waker: None,
+			closed: false,
+		}));
+		(ByteRingWriter(Arc::clone(&shared)), ByteRingReader { shared, id: 0 })
+	}
+
+	/// Get number of readable bytes
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Get maximum number of bytes this ring can hold
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Get number of bytes that can be written without discarding
+	pub fn free(&self) -> usize {
+		self.capacity - self.len
+	}
+
+	/// Check if this ring is empty
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Check if this ring is full
+	pub fn is_full(&self) -> bool {
+		self.len == self.capacity
+	}
+
+	/// Get readable bytes as 2 slices; first slice contains oldest bytes
+	pub fn as_slices(&self) -> (&[u8], &[u8]) {
+		let first = self.len.min(self.buf.len() - self.head);
+		(&self.buf[self.head..self.head + first], &self.buf[..self.len - first])
+	}
+
+	/// Get free space as 2 slices; use [ByteRing::commit] after writing into it
+	pub fn free_slices_mut(&mut self) -> (&mut [u8], &mut [u8]) {
+		self.reserve(self.free());
+		self.allocated_free_slices_mut()
+	}
+
+	/// make sure `additional` bytes can be written without reallocation; buffer never exceed capacity
+	fn reserve(&mut self, additional: usize) {
+		let need = self.len + additional;
+		if need <= self.buf.len() {
+			return;
+		}
+		let mut buf = vec![0; need.max(self.buf.len() * 2).min(self.capacity)].into_boxed_slice();
+		let (first, second) = self.as_slices();
+		buf[..first.len()].copy_from_slice(first);
+		buf[first.len()..self.len].copy_from_slice(second);
+		self.buf = buf;
+		self.head = 0;
+	}
+
+	/// free space of allocated buffer
+	fn allocated_free_slices_mut(&mut self) -> (&mut [u8], &mut [u8]) {
+		let cap = self.buf.len();
+		if cap == 0 {
+			return (&mut [], &mut []);
+		}
+		let tail = (self.head + self.len) % cap;
+		let free = cap - self.len;
+		let first = free.min(cap - tail);
+		let (left, right) = self.buf.split_at_mut(tail);
+		(&mut right[..first], &mut left[..free - first])
+	}
+
+	/// Mark `n` bytes of [ByteRing::free_slices_mut] as readable
+	pub fn commit(&mut self, n: usize) {
+		assert!(n <= self.buf.len() - self.len);
+		self.len += n;
+	}
+
+	/// Discard `n` oldest bytes
+	pub fn consume(&mut self, n: usize) {
+		assert!(n <= self.len);
+		self.len -= n;
+		self.head = if self.len == 0 { 0 } else { (self.head + n) % self.buf.len() };
+	}
+
+	/// Remove all bytes
+	pub fn clear(&mut self) {
+		self.head = 0;
+		self.len = 0;
+	}
+
+	/// Write all bytes and discard oldest bytes if there is not enough space  
+	/// return number of discarded bytes
+	pub fn write_overwrite(&mut self, mut data: &[u8]) -> usize {
+		let cap = self.capacity;
+		let mut discarded = 0;
+		if data.len() > cap {
+			discarded += data.len() - cap;
+			data = &data[data.len() - cap..];
+		}
+		let overflow = data.len().saturating_sub(self.free());
+		self.consume(overflow);
+		self.write_slice(data);
+		discarded + overflow
+	}
+
+	/// write as much as possible; return number of written bytes
+	fn write_slice(&mut self, data: &[u8]) -> usize {
+		self.reserve(data.len().min(self.free()));
+		let (first, second) = self.allocated_free_slices_mut();
+		let a = data.len().min(first.len());
+		first[..a].copy_from_slice(&data[..a]);
+		let b = (data.len() - a).min(second.len());
+		second[..b].copy_from_slice(&data[a..a + b]);
+		self.commit(a + b);
+		a + b
+	}
+
+	/// copy as much as possible after skipping `skip` oldest bytes; return number of copied bytes
+	fn peek_slice(&self, skip: usize, buf: &mut [u8]) -> usize {
+		let (first, second) = self.as_slices();
+		let (first, second) = if skip < first.len() { (&first[skip..], second) } else { (&[][..], &second[skip - first.len()..]) };
+		let a = buf.len().min(first.len());
+		buf[..a].copy_from_slice(&first[..a]);
+		let b = (buf.len() - a).min(second.len());
+		buf[a..a + b].copy_from_slice(&second[..b]);
+		a + b
+	}
+
+	/// read as much as possible; return number of read bytes
+	fn read_slice(&mut self, buf: &mut [u8]) -> usize {
+		let n = self.peek_slice(0, buf);
+		self.consume(n);
+		n
+	}
+}
+
+impl Read for ByteRing {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		Ok(self.read_slice(buf))
+	}
+}
+
+impl BufRead for ByteRing {
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		Ok(self.as_slices().0)
+	}
+
+	fn consume(&mut self, amt: usize) {
+		ByteRing::consume(self, amt)
+	}
+}
+
+impl Write for ByteRing {
+	fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+		if !buf.is_empty() && self.is_full() {
+			return Err(ErrorKind::WouldBlock.into());
+		}
+		Ok(self.write_slice(buf))
+	}
+
+	fn flush(&mut self) -> io::Result<()> {
+		Ok(())
+	}
+}
+
+/// read position of each reader
+struct Cursor {
+	/// number of bytes read since pipe was created
+	pos: u64,
+	waker: Option<Waker>,
+}
+
+struct Shared {
+	ring: ByteRing,
+	/// number of bytes consumed from ring since pipe was created
+	base: u64,
+	/// indexed by reader id; None if reader was dropped
+	readers: Vec<Option<Cursor>>,
+	write_waker: Option<Waker>,
+	/// writer was closed or dropped
+	closed: bool,
+}
+
+impl Shared {
+	fn wake_readers(&mut self) {
+		for waker in self.readers.iter_mut().flatten().filter_map(|it| it.waker.take()) {
+			waker.wake();
+		}
+	}
+
+	/// discard bytes every reader has read
+	fn release(&mut self) {
+		let Some(min) = self.readers.iter().flatten().map(|it| it.pos).min() else { return; };
+		let n = (min - self.base) as usize;
+		if n > 0 {
+			self.ring.consume(n);
+			self.base = min;
+			if let Some(waker) = self.write_waker.take() { waker.wake(); }
+		}
+	}
+}
+
+fn lock(shared: &Mutex<Shared>) -> MutexGuard<'_, Shared> {
+	shared.lock().unwrap_or_else(|it| it.into_inner())
+}
+
+/// Writing half of [ByteRing::pipe]; writer will wait when ring is full
+pub struct ByteRingWriter(Arc<Mutex<Shared>>);
+
+/// Reading half of [ByteRing::pipe]; reader will receive EOF after writer was closed and every byte was read  
+/// cloned reader start at same position and read independently
+pub struct ByteRingReader {
+	shared: Arc<Mutex<Shared>>,
+	id: usize,
+}
+
+impl ByteRingWriter {
+	/// Try to write bytes into ring; return `Pending` if ring is full  
+	/// return `BrokenPipe` after this writer was closed or every reader was dropped
+	pub fn poll_write(&self, cx: &mut Context<'_>, data: &[u8]) -> Poll<io::Result<usize>> {
+		let mut shared = lock(&self.0);
+		if shared.closed || shared.readers.iter().all(Option::is_none) {
+			return Poll::Ready(Err(ErrorKind::BrokenPipe.into()));
+		}
+		if data.is_empty() {
+			return Poll::Ready(Ok(0));
+		}
+		if shared.ring.is_full() {
+			shared.write_waker = Some(cx.waker().clone());
+			return Poll::Pending;
+		}
+		let n = shared.ring.write_slice(data);
+		shared.wake_readers();
+		Poll::Ready(Ok(n))
+	}
+
+	/// Close this pipe; reader will receive EOF after remaining bytes
+	pub fn close(&self) {
+		let mut shared = lock(&self.0);
+		shared.closed = true;
+		shared.wake_readers();
+	}
+}
+
+impl Drop for ByteRingWriter {
+	fn drop(&mut self) {
+		self.close();
+	}
+}
+
+impl ByteRingReader {
+	/// Try to read bytes from ring; return `Pending` if ring is empty and writer is still open
+	pub fn poll_read(&self, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+		let mut shared = lock(&self.shared);
+		if buf.is_empty() {
+			return Poll::Ready(Ok(0));
+		}
+		let shared = &mut *shared;
+		let cursor = shared.readers[self.id].as_mut().unwrap();
+		let skip = (cursor.pos - shared.base) as usize;
+		if skip == shared.ring.len() {
+			if shared.closed {
+				return Poll::Ready(Ok(0));
+			}
+			cursor.waker = Some(cx.waker().clone());
+			return Poll::Pending;
+		}
+		let n = shared.ring.peek_slice(skip, buf);
+		cursor.pos += n as u64;
+		shared.release();
+		Poll::Ready(Ok(n))
+	}
+}
+
+impl Clone for ByteRingReader {
+	fn clone(&self) -> Self {
+		let mut shared = lock(&self.shared);
+		let pos = shared.readers[self.id].as_ref().unwrap().pos;
+		let cursor = Some(Cursor { pos, waker: None });
+		let id = match shared.readers.iter().position(Option::is_none) {
+			Some(id) => {
+				shared.readers[id] = cursor;
+				id
+			}
+			None => {
+				shared.readers.push(cursor);
+				shared.readers.len() - 1
+			}
+		};
+		Self { shared: Arc::clone(&self.shared), id }
+	}
+}
+
+impl Drop for ByteRingReader {
+	fn drop(&mut self) {
+		let mut shared = lock(&self.shared);
+		shared.readers[self.id] = None;
+		shared.release();
+		// wake writer so it can fail with BrokenPipe when there is no reader left
+		if let Some(waker) = shared.write_waker.take() { waker.wake(); }
+	}
+}
+
+#[cfg(feature = "async")]
+impl futures::io::AsyncWrite for ByteRingWriter {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		ByteRingWriter::poll_write(&self, cx, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_close(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.close();
+		Poll::Ready(Ok(()))
+	}
+}
+
+#[cfg(feature = "async")]
+impl futures::io::AsyncRead for ByteRingReader {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut [u8]) -> Poll<io::Result<usize>> {
+		ByteRingReader::poll_read(&self, cx, buf)
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncWrite for ByteRingWriter {
+	fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+		ByteRingWriter::poll_write(&self, cx, buf)
+	}
+
+	fn poll_flush(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		Poll::Ready(Ok(()))
+	}
+
+	fn poll_shutdown(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+		self.close();
+		Poll::Ready(Ok(()))
+	}
+}
+
+#[cfg(feature = "tokio")]
+impl tokio::io::AsyncRead for ByteRingReader {
+	fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut tokio::io::ReadBuf<'_>) -> Poll<io::Result<()>> {
+		let n = match ByteRingReader::poll_read(&self, cx, buf.initialize_unfilled()) {
+			Poll::Ready(Ok(n)) => n,
+			Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+			Poll::Pending => return Poll::Pending,
+		};
+		buf.advance(n);
+		Poll::Ready(Ok(()))
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::io::{BufRead, Write};
+
+	use crate::collection::ByteRing;
+
+	#[test]
+	fn test_byte_ring() {
+		let mut ring = ByteRing::new(4);
+		assert_eq!(ring.write(b"abc").unwrap(), 3);
+		ring.consume(2);
+		// wrap around
+		assert_eq!(ring.write(b"defg").unwrap(), 3);
+		assert!(ring.write(b"g").is_err());
+		assert_eq!(ring.as_slices(), (&b"cd"[..], &b"ef"[..]));
+		assert_eq!(ring.fill_buf().unwrap(), b"cd");
+		BufRead::consume(&mut ring, 2);
+		assert_eq!(ring.fill_buf().unwrap(), b"ef");
+
+		let (first, second) = ring.free_slices_mut();
+		assert_eq!((first.len(), second.len()), (2, 0));
+		first.copy_from_slice(b"gh");
+		ring.commit(2);
+		assert_eq!(ring.as_slices(), (&b"efgh"[..], &b""[..]));
+
+		// buffer of lazy ring grow as bytes are written
+		let mut ring = ByteRing::lazy(6);
+		assert_eq!(ring.write(b"abc").unwrap(), 3);
+		ring.consume(2);
+		assert_eq!(ring.write(b"defghi").unwrap(), 5);
+		assert_eq!(ring.buf.len(), 6);
+		assert_eq!(ring.write_overwrite(b"jk"), 2);
+		let (first, second) = ring.as_slices();
+		assert_eq!([first, second].concat(), b"efghjk");
+	}
+
+	#[cfg(feature = "async")]
+	#[test]
+	fn test_byte_ring_pipe() {
+		use futures::executor::block_on;
+		use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+		let (mut writer, mut reader) = ByteRing::pipe(16);
+		writer.close();
+		assert_eq!(block_on(writer.write_all(b"a")).unwrap_err().kind(), std::io::ErrorKind::BrokenPipe);
+		let mut out = Vec::new();
+		assert_eq!(block_on(reader.read_to_end(&mut out)).unwrap(), 0);
+
+		let (mut writer, mut reader) = ByteRing::pipe(4);
+		let handle = std::thread::spawn(move || block_on(async move {
+			writer.write_all(b"Hello world").await.unwrap();
+		}));
+		let mut out = Vec::new();
+		block_on(reader.read_to_end(&mut out)).unwrap();
+		handle.join().unwrap();
+		assert_eq!(out, b"Hello world");
+	}
+
+	#[cfg(feature = "async")]
+	#[test]
+	fn test_byte_ring_fan_out() {
+		use futures::executor::block_on;
+		use futures::future::join3;
+		use futures::io::{AsyncReadExt, AsyncWriteExt};
+
+		let (mut writer, mut reader) = ByteRing::pipe(4);
+		block_on(writer.write_all(b"ab")).unwrap();
+		let mut first = [0; 1];
+		block_on(reader.read_exact(&mut first)).unwrap();
+		// clone start at position of original reader
+		let mut other = reader.clone();
+		let mut out = (Vec::new(), Vec::new());
+		// ring is smaller than data, so writer has to wait for both readers
+		let (_, a, b) = block_on(join3(
+			async move {
+				writer.write_all(b"cdefghijkl").await.unwrap();
+				writer.close();
+			},
+			reader.read_to_end(&mut out.0),
+			other.read_to_end(&mut out.1),
+		));
+		assert_eq!((a.unwrap(), b.unwrap()), (11, 11));
+		assert_eq!(out.0, b"bcdefghijkl");
+		assert_eq!(out.1, b"bcdefghijkl");
+
+		let (mut writer, reader) = ByteRing::pipe(4);
+		let other = reader.clone();
+		drop(reader);
+		assert!(block_on(writer.write_all(b"ab")).is_ok());
+		drop(other);
+		assert!(block_on(writer.write_all(b"cd")).is_err());
+	}
+}
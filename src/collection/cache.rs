@@ -0,0 +1,593 @@
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{Duration, Instant};
+
+use crate::collection::{Clock, SystemClock};
+
+const NIL: usize = usize::MAX;
+
+/// Eviction policy of [Cache]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Policy {
+	/// Evict least recently used entry
+	#[default]
+	Lru,
+	/// Evict least frequently used entry; ties are broken by recency
+	Lfu,
+}
+
+/// Reason of eviction passed to [Cache::on_evict] callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Evicted {
+	/// Entry was evicted to make room for other entries
+	Capacity,
+	/// Entry was older than its time to live
+	Expired,
+}
+
+struct Entry<K, V> {
+	key: K,
+	value: V,
+	weight: usize,
+	expire: Option<Instant>,
+	bucket: usize,
+	prev: usize,
+	next: usize,
+}
+
+/// List of entries with same frequency; head is most recently used
+struct Bucket {
+	freq: u64,
+	head: usize,
+	tail: usize,
+	prev: usize,
+	next: usize,
+}
+
+type Weigher<K, V> = Box<dyn Fn(&K, &V) -> usize + Send + Sync>;
+type Listener<K, V> = Box<dyn FnMut(K, V, Evicted) + Send>;
+
+/// Cache with O(1) get/put and bounded total weight
+/// + every entry has weight of 1 unless [Cache::with_weigher] is used (eg. to count bytes)
+/// + entries can expire after time to live; expired entries are removed lazily or by [Cache::purge_expired]
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::Cache;
+/// let mut cache = Cache::new(2);
+/// cache.put("a", 1);
+/// cache.put("b", 2);
+/// cache.get("a");
+/// cache.put("c", 3);
+/// // "b" is least recently used
+/// assert!(!cache.contains_key("b"));
+/// assert_eq!(cache.get("a"), Some(&1));
+/// ```
+pub struct Cache<K, V, C = SystemClock> {
+	map: HashMap<K, usize>,
+	entries: Vec<Option<Entry<K, V>>>,
+	free_entries: Vec<usize>,
+	buckets: Vec<Bucket>,
+	free_buckets: Vec<usize>,
+	/// bucket with lowest frequency
+	first: usize,
+	policy: Policy,
+	capacity: usize,
+	weight: usize,
+	ttl: Option<Duration>,
+	weigher: Option<Weigher<K, V>>,
+	listener: Option<Listener<K, V>>,
+	clock: C,
+}
+
+impl<K: Hash + Eq + Clone, V> Cache<K, V> {
+	/// Create new LRU cache that hold entries up to total weight of `capacity`
+	pub fn new(capacity: usize) -> Self {
+		Self::with_clock(capacity, SystemClock)
+	}
+}
+
+impl<K: Hash + Eq + Clone, V, C: Clock> Cache<K, V, C> {
+	/// Create new cache that use custom clock to check time to live
+	pub fn with_clock(capacity: usize, clock: C) -> Self {
+		Self {
+			map: HashMap::new(),
+			entries: Vec::new(),
+			free_entries: Vec::new(),
+			buckets: Vec::new(),
+			free_buckets: Vec::new(),
+			first: NIL,
+			policy: Policy::Lru,
+			capacity,
+			weight: 0,
+			ttl: None,
+			weigher: None,
+			listener: None,
+			clock,
+		}
+	}
+
+	/// Set eviction policy; must be called before inserting any entry
+	#[must_use]
+	pub fn with_policy(mut self, policy: Policy) -> Self {
+		assert!(self.is_empty());
+		self.policy = policy;
+		self
+	}
+
+	/// Use `weigher(key, value)` as weight of entry instead of 1
+	#[must_use]
+	pub fn with_weigher(mut self, weigher: impl Fn(&K, &V) -> usize + Send + Sync + 'static) -> Self {
+		self.weigher = Some(Box::new(weigher));
+		self
+	}
+
+	/// Set default time to live of entries
+	#[must_use]
+	pub fn with_ttl(mut self, ttl: Duration) -> Self {
+		self.ttl = Some(ttl);
+		self
+	}
+
+	/// Call `listener` with entry that was evicted by capacity or expiration
+	/// entries removed by [Cache::remove], [Cache::clear] or replaced by [Cache::put] are not passed to listener
+	#[must_use]
+	pub fn on_evict(mut self, listener: impl FnMut(K, V, Evicted) + Send + 'static) -> Self {
+		self.listener = Some(Box::new(listener));
+		self
+	}
+
+	/// Get number of entries (including expired entries that was not removed yet)
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	/// Check if there is no entry
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	/// Get total weight of entries
+	pub fn weight(&self) -> usize {
+		self.weight
+	}
+
+	/// Get maximum total weight
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
+	/// Get eviction policy
+	pub fn policy(&self) -> Policy {
+		self.policy
+	}
+
+	/// Insert entry with default time to live; return old value of `key`
+	pub fn put(&mut self, key: K, value: V) -> Option<V> {
+		let expire = self.ttl.map(|ttl| self.clock.now() + ttl);
+		self.insert(key, value, expire)
+	}
+
+	/// Insert entry that expire after `ttl`; return old value of `key`
+	pub fn put_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+		let expire = self.clock.now() + ttl;
+		self.insert(key, value, Some(expire))
+	}
+
+	fn insert(&mut self, key: K, value: V, expire: Option<Instant>) -> Option<V> {
+		let weight = self.weigher.as_ref().map_or(1, |it| it(&key, &value));
+		if let Some(&idx) = self.map.get(&key) {
+			if weight > self.capacity {
+				let old = self.remove_entry(idx);
+				self.notify(key, value, Evicted::Capacity);
+				return Some(old.value);
+			}
+			let entry = self.entries[idx].as_mut().unwrap();
+			let old = std::mem::replace(&mut entry.value, value);
+			self.weight = self.weight - entry.weight + weight;
+			entry.weight = weight;
+			entry.expire = expire;
+			self.touch(idx);
+			self.evict_to(self.capacity);
+			return Some(old);
+		}
+		if weight > self.capacity {
+			self.notify(key, value, Evicted::Capacity);
+			return None;
+		}
+		self.evict_to(self.capacity - weight);
+		let bucket = if self.first != NIL && self.buckets[self.first].freq == 1 {
+			self.first
+		} else {
+			self.alloc_bucket(1, NIL, self.first)
+		};
+		let entry = Entry { key: key.clone(), value, weight, expire, bucket, prev: NIL, next: NIL };
+		let idx = match self.free_entries.pop() {
+			Some(idx) => {
+				self.entries[idx] = Some(entry);
+				idx
+			}
+			None => {
+				self.entries.push(Some(entry));
+				self.entries.len() - 1
+			}
+		};
+		self.attach(idx, bucket);
+		self.map.insert(key, idx);
+		self.weight += weight;
+		None
+	}
+
+	/// Get value and mark it as used; expired entry will be removed
+	pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let idx = self.lookup(key)?;
+		self.touch(idx);
+		self.entries[idx].as_ref().map(|it| &it.value)
+	}
+
+	/// Get mutable value and mark it as used; weight of entry will not be updated
+	pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let idx = self.lookup(key)?;
+		self.touch(idx);
+		self.entries[idx].as_mut().map(|it| &mut it.value)
+	}
+
+	/// Get value without marking it as used
+	pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let entry = self.entries[*self.map.get(key)?].as_ref()?;
+		if self.is_expired(entry) { None } else { Some(&entry.value) }
+	}
+
+	/// Check if cache contains unexpired entry of `key`
+	pub fn contains_key<Q>(&self, key: &Q) -> bool
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.peek(key).is_some()
+	}
+
+	/// Remove entry and return its value
+	pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let idx = self.lookup(key)?;
+		Some(self.remove_entry(idx).value)
+	}
+
+	/// Remove all expired entries; return number of removed entries
+	pub fn purge_expired(&mut self) -> usize {
+		let now = self.clock.now();
+		let expired = self.entries.iter().enumerate()
+			.filter(|(_, it)| it.as_ref().is_some_and(|it| it.expire.is_some_and(|expire| expire <= now)))
+			.map(|(idx, _)| idx)
+			.collect::<Vec<_>>();
+		for idx in &expired {
+			let entry = self.remove_entry(*idx);
+			self.notify(entry.key, entry.value, Evicted::Expired);
+		}
+		expired.len()
+	}
+
+	/// Remove all entries
+	pub fn clear(&mut self) {
+		self.map.clear();
+		self.entries.clear();
+		self.free_entries.clear();
+		self.buckets.clear();
+		self.free_buckets.clear();
+		self.first = NIL;
+		self.weight = 0;
+	}
+
+	fn is_expired(&self, entry: &Entry<K, V>) -> bool {
+		entry.expire.is_some_and(|it| it <= self.clock.now())
+	}
+
+	/// find index of `key`; expired entry will be removed
+	fn lookup<Q>(&mut self, key: &Q) -> Option<usize>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let idx = *self.map.get(key)?;
+		if self.is_expired(self.entries[idx].as_ref()?) {
+			let entry = self.remove_entry(idx);
+			self.notify(entry.key, entry.value, Evicted::Expired);
+			return None;
+		}
+		Some(idx)
+	}
+
+	fn notify(&mut self, key: K, value: V, reason: Evicted) {
+		if let Some(listener) = &mut self.listener {
+			listener(key, value, reason);
+		}
+	}
+
+	/// evict entries until total weight is not greater than `weight`
+	fn evict_to(&mut self, weight: usize) {
+		while self.weight > weight && self.first != NIL {
+			let idx = self.buckets[self.first].tail;
+			let entry = self.remove_entry(idx);
+			self.notify(entry.key, entry.value, Evicted::Capacity);
+		}
+	}
+
+	fn remove_entry(&mut self, idx: usize) -> Entry<K, V> {
+		self.detach(idx);
+		let entry = self.entries[idx].take().unwrap();
+		self.free_entries.push(idx);
+		self.map.remove(&entry.key);
+		self.weight -= entry.weight;
+		entry
+	}
+
+	/// mark entry as used
+	fn touch(&mut self, idx: usize) {
+		let bucket = self.entries[idx].as_ref().unwrap().bucket;
+		match self.policy {
+			Policy::Lru => {
+				if self.buckets[bucket].head != idx {
+					self.unlink(idx);
+					self.attach(idx, bucket);
+				}
+			}
+			Policy::Lfu => {
+				let freq = self.buckets[bucket].freq.saturating_add(1);
+				let next = self.buckets[bucket].next;
+				let target = if next != NIL && self.buckets[next].freq == freq {
+					next
+				} else {
+					self.alloc_bucket(freq, bucket, next)
+				};
+				self.detach(idx);
+				self.attach(idx, target);
+			}
+		}
+	}
+
+	/// create bucket between `prev` and `next`
+	fn alloc_bucket(&mut self, freq: u64, prev: usize, next: usize) -> usize {
+		let bucket = Bucket { freq, head: NIL, tail: NIL, prev, next };
+		let idx = match self.free_buckets.pop() {
+			Some(idx) => {
+				self.buckets[idx] = bucket;
+				idx
+			}
+			None => {
+				self.buckets.push(bucket);
+				self.buckets.len() - 1
+			}
+		};
+		if prev == NIL { self.first = idx; } else { self.buckets[prev].next = idx; }
+		if next != NIL { self.buckets[next].prev = idx; }
+		idx
+	}
+
+	/// push entry to head of bucket
+	fn attach(&mut self, idx: usize, bucket: usize) {
+		let head = self.buckets[bucket].head;
+		let entry = self.entries[idx].as_mut().unwrap();
+		entry.bucket = bucket;
+		entry.prev = NIL;
+		entry.next = head;
+		if head == NIL {
+			self.buckets[bucket].tail = idx;
+		} else {
+			self.entries[head].as_mut().unwrap().prev = idx;
+		}
+		self.buckets[bucket].head = idx;
+	}
+
+	/// remove entry from its bucket without freeing empty bucket
+	fn unlink(&mut self, idx: usize) {
+		let entry = self.entries[idx].as_ref().unwrap();
+		let (bucket, prev, next) = (entry.bucket, entry.prev, entry.next);
+		if prev == NIL { self.buckets[bucket].head = next; } else { self.entries[prev].as_mut().unwrap().next = next; }
+		if next == NIL { self.buckets[bucket].tail = prev; } else { self.entries[next].as_mut().unwrap().prev = prev; }
+	}
+
+	/// remove entry from its bucket and free bucket if it's empty
+	fn detach(&mut self, idx: usize) {
+		self.unlink(idx);
+		let bucket = self.entries[idx].as_ref().unwrap().bucket;
+		let Bucket { head, prev, next, .. } = self.buckets[bucket];
+		if head == NIL {
+			if prev == NIL { self.first = next; } else { self.buckets[prev].next = next; }
+			if next != NIL { self.buckets[next].prev = prev; }
+			self.free_buckets.push(bucket);
+		}
+	}
+}
+
+/// Thread-safe [Cache] that split entries into multiple shards to reduce lock contention
+/// eviction listener will be called while shard is locked
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::ShardedCache;
+/// let cache = ShardedCache::new(4, 100);
+/// cache.put(1, "one".to_string());
+/// assert_eq!(cache.get(&1), Some("one".to_string()));
+/// assert_eq!(cache.get_with(&1, |it| it.len()), Some(3));
+/// ```
+pub struct ShardedCache<K, V, C = SystemClock> {
+	shards: Box<[Mutex<Cache<K, V, C>>]>,
+	hasher: RandomState,
+}
+
+impl<K: Hash + Eq + Clone, V> ShardedCache<K, V> {
+	/// Create new sharded LRU cache; `capacity` is divided equally between shards
+	pub fn new(shards: usize, capacity: usize) -> Self {
+		assert!(shards > 0);
+		let capacity = capacity.div_ceil(shards);
+		Self::from_fn(shards, |_| Cache::new(capacity))
+	}
+}
+
+impl<K: Hash + Eq + Clone, V, C: Clock> ShardedCache<K, V, C> {
+	/// Create new sharded cache and use result from `init(index)` as each shard
+	pub fn from_fn(shards: usize, init: impl FnMut(usize) -> Cache<K, V, C>) -> Self {
+		assert!(shards > 0);
+		Self {
+			shards: (0..shards).map(init).map(Mutex::new).collect(),
+			hasher: RandomState::new(),
+		}
+	}
+
+	/// Lock shard that contains `key`
+	pub fn shard<Q>(&self, key: &Q) -> MutexGuard<'_, Cache<K, V, C>>
+		where Q: Hash + ?Sized {
+		let idx = self.hasher.hash_one(key) as usize % self.shards.len();
+		self.shards[idx].lock().unwrap_or_else(|it| it.into_inner())
+	}
+
+	fn shards(&self) -> impl Iterator<Item=MutexGuard<'_, Cache<K, V, C>>> {
+		self.shards.iter().map(|it| it.lock().unwrap_or_else(|it| it.into_inner()))
+	}
+
+	/// Insert entry with default time to live; return old value of `key`
+	pub fn put(&self, key: K, value: V) -> Option<V> {
+		self.shard(&key).put(key, value)
+	}
+
+	/// Insert entry that expire after `ttl`; return old value of `key`
+	pub fn put_with_ttl(&self, key: K, value: V, ttl: Duration) -> Option<V> {
+		self.shard(&key).put_with_ttl(key, value, ttl)
+	}
+
+	/// Get clone of value and mark it as used
+	pub fn get<Q>(&self, key: &Q) -> Option<V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized,
+		      V: Clone {
+		self.shard(key).get(key).cloned()
+	}
+
+	/// Call `f` with value and mark it as used
+	pub fn get_with<Q, R>(&self, key: &Q, f: impl FnOnce(&V) -> R) -> Option<R>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.shard(key).get(key).map(f)
+	}
+
+	/// Check if cache contains unexpired entry of `key`
+	pub fn contains_key<Q>(&self, key: &Q) -> bool
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.shard(key).contains_key(key)
+	}
+
+	/// Remove entry and return its value
+	pub fn remove<Q>(&self, key: &Q) -> Option<V>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.shard(key).remove(key)
+	}
+
+	/// Get number of entries in every shard
+	pub fn len(&self) -> usize {
+		self.shards().map(|it| it.len()).sum()
+	}
+
+	/// Check if every shard is empty
+	pub fn is_empty(&self) -> bool {
+		self.shards().all(|it| it.is_empty())
+	}
+
+	/// Get total weight of every shard
+	pub fn weight(&self) -> usize {
+		self.shards().map(|it| it.weight()).sum()
+	}
+
+	/// Remove expired entries of every shard; return number of removed entries
+	pub fn purge_expired(&self) -> usize {
+		self.shards().map(|mut it| it.purge_expired()).sum()
+	}
+
+	/// Remove all entries
+	pub fn clear(&self) {
+		self.shards().for_each(|mut it| it.clear());
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::cell::Cell;
+	use std::sync::{Arc, Mutex};
+	use std::time::{Duration, Instant};
+
+	use crate::collection::{Cache, Evicted, Policy, ShardedCache};
+
+	#[test]
+	fn test_cache_policy() {
+		let evicted = Arc::new(Mutex::new(Vec::new()));
+		let log = Arc::clone(&evicted);
+		let mut lru = Cache::new(3).on_evict(move |k, _, reason| log.lock().unwrap().push((k, reason)));
+		for i in 0..3 { lru.put(i, i); }
+		lru.get(&0);
+		lru.put(3, 3);
+		lru.put(4, 4);
+		assert_eq!(*evicted.lock().unwrap(), vec![(1, Evicted::Capacity), (2, Evicted::Capacity)]);
+		assert_eq!(lru.put(0, 10), Some(0));
+		assert_eq!(lru.len(), 3);
+
+		let mut lfu = Cache::new(3).with_policy(Policy::Lfu);
+		for i in 0..3 { lfu.put(i, i); }
+		for _ in 0..3 { lfu.get(&0); }
+		lfu.get(&1);
+		lfu.get(&2);
+		lfu.get(&1);
+		// 2 has lowest frequency
+		lfu.put(3, 3);
+		assert!(!lfu.contains_key(&2));
+		// new entry has lowest frequency
+		lfu.put(4, 4);
+		assert!(!lfu.contains_key(&3));
+		assert!(lfu.contains_key(&0) && lfu.contains_key(&1) && lfu.contains_key(&4));
+		assert_eq!(lfu.remove(&0), Some(0));
+		assert_eq!(lfu.len(), 2);
+	}
+
+	#[test]
+	fn test_cache_weight_ttl() {
+		let now = Cell::new(Instant::now());
+		let mut cache = Cache::with_clock(10, || now.get())
+			.with_weigher(|_, v: &Vec<u8>| v.len())
+			.with_ttl(Duration::from_secs(10));
+		cache.put("a", vec![0; 4]);
+		cache.put("b", vec![0; 4]);
+		cache.put("c", vec![0; 4]);
+		assert_eq!((cache.len(), cache.weight()), (2, 8));
+		assert!(cache.peek("a").is_none());
+		// too large to fit
+		cache.put("d", vec![0; 11]);
+		assert_eq!((cache.len(), cache.weight()), (2, 8));
+		cache.put_with_ttl("e", vec![], Duration::from_secs(30));
+		now.set(now.get() + Duration::from_secs(20));
+		assert_eq!(cache.get("b"), None);
+		assert_eq!(cache.purge_expired(), 1);
+		assert_eq!(cache.get("e"), Some(&vec![]));
+		assert_eq!((cache.len(), cache.weight()), (1, 0));
+	}
+
+	#[test]
+	fn test_sharded_cache() {
+		let cache = Arc::new(ShardedCache::new(4, 1000));
+		let handles = (0..4).map(|t| {
+			let cache = Arc::clone(&cache);
+			std::thread::spawn(move || {
+				for i in 0..100 { cache.put(t * 100 + i, i); }
+			})
+		}).collect::<Vec<_>>();
+		for handle in handles { handle.join().unwrap(); }
+		assert_eq!(cache.len(), 400);
+		assert_eq!(cache.get(&250), Some(50));
+		cache.clear();
+		assert!(cache.is_empty());
+	}
+}
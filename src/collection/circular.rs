@@ -0,0 +1,110 @@
+#[cfg(feature = "alloc")]
+use crate::collection::CircularVec;
+use crate::collection::CircularArray;
+
+/// Common behavior of fixed size buffer that discard oldest element when full
+pub trait Circular<T> {
+	/// Append value to ends of buffer; if buffer is full it will return oldest element
+	fn push(&mut self, item: T) -> Option<T>;
+
+	/// Try to remove oldest element from buffer
+	fn pop(&mut self) -> Option<T>;
+
+	/// Get number of elements in buffer
+	fn len(&self) -> usize;
+
+	/// Get maximum number of elements buffer can hold
+	fn capacity(&self) -> usize;
+
+	/// Check if buffer is empty
+	fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Check if buffer is full
+	fn is_full(&self) -> bool {
+		self.len() == self.capacity()
+	}
+}
+
+#[cfg(feature = "alloc")]
+impl<T> Circular<T> for CircularVec<T> {
+	#[inline]
+	fn push(&mut self, item: T) -> Option<T> {
+		CircularVec::push(self, item)
+	}
+
+	#[inline]
+	fn pop(&mut self) -> Option<T> {
+		CircularVec::pop(self)
+	}
+
+	#[inline]
+	fn len(&self) -> usize {
+		CircularVec::len(self)
+	}
+
+	#[inline]
+	fn capacity(&self) -> usize {
+		CircularVec::capacity(self)
+	}
+}
+
+impl<T, const N: usize> Circular<T> for CircularArray<T, N> {
+	#[inline]
+	fn push(&mut self, item: T) -> Option<T> {
+		CircularArray::push(self, item)
+	}
+
+	#[inline]
+	fn pop(&mut self) -> Option<T> {
+		CircularArray::pop(self)
+	}
+
+	#[inline]
+	fn len(&self) -> usize {
+		CircularArray::len(self)
+	}
+
+	#[inline]
+	fn capacity(&self) -> usize {
+		N
+	}
+}
+
+#[cfg(all(test, feature = "std"))]
+mod test {
+	use std::rc::Rc;
+
+	use crate::collection::{Circular, CircularArray, CircularVec};
+
+	/// `buf` must be empty with capacity 4
+	fn check_circular<C: Circular<Rc<i32>>>(mut buf: C) {
+		let counter = Rc::new(0);
+		assert!(buf.is_empty());
+		assert_eq!(buf.capacity(), 4);
+		for _ in 0..4 { assert!(buf.push(Rc::clone(&counter)).is_none()); }
+		assert!(buf.is_full());
+		// evicted element is returned and dropped by caller
+		drop(buf.push(Rc::clone(&counter)));
+		assert_eq!(Rc::strong_count(&counter), 5);
+		drop(buf.pop());
+		assert_eq!(buf.len(), 3);
+		assert_eq!(Rc::strong_count(&counter), 4);
+		drop(buf);
+		assert_eq!(Rc::strong_count(&counter), 1);
+	}
+
+	#[test]
+	fn test_circular() {
+		check_circular(CircularVec::new(4));
+		check_circular(CircularArray::<_, 4>::new());
+
+		let mut arr = CircularArray::<i32, 3>::new();
+		for i in 0..5 { arr.push(i); }
+		assert_eq!(arr.as_slices(), (&[2][..], &[3, 4][..]));
+		assert_eq!(arr.get(2), Some(&4));
+		arr.clear();
+		assert!(arr.is_empty());
+	}
+}
@@ -0,0 +1,168 @@
+use core::mem;
+use core::mem::MaybeUninit;
+use core::{ptr, slice};
+
+/// Heap-free version of [CircularVec](crate::collection::CircularVec) that store elements inline  
+/// this struct doesn't require `std` or `alloc` and can be created in `const` context
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::CircularArray;
+/// static EMPTY: CircularArray<u32, 3> = CircularArray::new();
+/// assert!(EMPTY.is_empty());
+/// let mut arr = CircularArray::<u32, 3>::new();
+/// arr.push(1);
+/// arr.push(2);
+/// arr.push(3);
+/// // oldest element will be returned when full
+/// assert_eq!(arr.push(4), Some(1));
+/// assert_eq!(arr.iter().copied().collect::<Vec<_>>(), vec![2, 3, 4]);
+/// ```
+pub struct CircularArray<T, const N: usize> {
+	data: [MaybeUninit<T>; N],
+	/// index of oldest element
+	head: usize,
+	len: usize,
+}
+
+impl<T, const N: usize> CircularArray<T, N> {
+	/// Create new empty circular array
+	pub const fn new() -> Self {
+		Self {
+			data: [const { MaybeUninit::uninit() }; N],
+			head: 0,
+			len: 0,
+		}
+	}
+
+	#[inline]
+	const fn index(&self, offset: usize) -> usize {
+		let idx = self.head + offset;
+		if idx >= N { idx - N } else { idx }
+	}
+
+	/// Append value to ends of array; if array is full it will return oldest element
+	pub fn push(&mut self, item: T) -> Option<T> {
+		if N == 0 {
+			return Some(item);
+		}
+		if self.len == N {
+			let last = mem::replace(&mut self.data[self.head], MaybeUninit::new(item));
+			self.head = self.index(1);
+			// element at head is always initialized when full
+			Some(unsafe { last.assume_init() })
+		} else {
+			let idx = self.index(self.len);
+			self.data[idx].write(item);
+			self.len += 1;
+			None
+		}
+	}
+
+	/// Try to remove oldest element from array
+	pub fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			return None;
+		}
+		let item = unsafe { self.data[self.head].assume_init_read() };
+		self.head = self.index(1);
+		self.len -= 1;
+		Some(item)
+	}
+
+	/// Get element at given position (0 is oldest element)
+	pub fn get(&self, index: usize) -> Option<&T> {
+		if index < self.len {
+			Some(unsafe { self.data[self.index(index)].assume_init_ref() })
+		} else {
+			None
+		}
+	}
+
+	/// Get length of this circular array
+	pub const fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Get maximum number of elements this circular array can hold
+	pub const fn capacity(&self) -> usize {
+		N
+	}
+
+	/// Check if this circular array is empty
+	pub const fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Check if this circular array is full
+	pub const fn is_full(&self) -> bool {
+		self.len == N
+	}
+
+	/// Get elements as 2 slices; first slice contains oldest elements
+	pub fn as_slices(&self) -> (&[T], &[T]) {
+		let (first, second) = self.ranges();
+		unsafe {
+			(
+				slice::from_raw_parts(self.data.as_ptr().add(first.0).cast::<T>(), first.1),
+				slice::from_raw_parts(self.data.as_ptr().cast::<T>(), second),
+			)
+		}
+	}
+
+	/// Get elements as 2 mutable slices; first slice contains oldest elements
+	pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+		let (first, second) = self.ranges();
+		let ptr = self.data.as_mut_ptr();
+		unsafe {
+			(
+				slice::from_raw_parts_mut(ptr.add(first.0).cast::<T>(), first.1),
+				slice::from_raw_parts_mut(ptr.cast::<T>(), second),
+			)
+		}
+	}
+
+	/// ((start, len) of first part, len of wrapped part)
+	#[inline]
+	fn ranges(&self) -> ((usize, usize), usize) {
+		let first = self.len.min(N - self.head);
+		((self.head, first), self.len - first)
+	}
+
+	/// Remove all elements
+	pub fn clear(&mut self) {
+		let (first, second) = self.as_mut_slices();
+		let (first, second) = (first as *mut [T], second as *mut [T]);
+		self.head = 0;
+		self.len = 0;
+		unsafe {
+			ptr::drop_in_place(first);
+			ptr::drop_in_place(second);
+		}
+	}
+
+	/// Take all data from this circular array into vec
+	#[cfg(feature = "std")]
+	pub fn take(&mut self) -> Vec<T> {
+		let mut out_vec = Vec::with_capacity(self.len);
+		while let Some(elem) = self.pop() { out_vec.push(elem); }
+		out_vec
+	}
+
+	/// Iterate elements from oldest to newest
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item=&T> + '_ {
+		let (first, second) = self.as_slices();
+		first.iter().chain(second.iter())
+	}
+}
+
+impl<T, const N: usize> Default for CircularArray<T, N> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T, const N: usize> Drop for CircularArray<T, N> {
+	fn drop(&mut self) {
+		self.clear();
+	}
+}
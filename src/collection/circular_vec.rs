@@ -1,4 +1,6 @@
-use std::mem;
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+use core::mem;
 
 use crate::new_vec;
 
@@ -7,9 +9,11 @@ use crate::new_vec;
 /// + store interval of data eg. load average every second size=15 will store latest 15 seconds
 /// + store lines output from another process
 pub struct CircularVec<T> {
+	/// allocated slots; may be shorter than `capacity` for [CircularVec::lazy]
 	vec: Vec<Option<T>>,
 	head: usize,
 	tail: usize,
+	capacity: usize,
 }
 
 impl<T> CircularVec<T> {
@@ -19,9 +23,29 @@ impl<T> CircularVec<T> {
 			vec: new_vec!(None; size),
 			head: 0,
 			tail: 0,
+			capacity: size,
 		}
 	}
 
+	/// Same as [CircularVec::new] but slots are allocated as elements are pushed;
+	/// useful when size come from untrusted input
+	pub fn lazy(size: usize) -> Self {
+		Self {
+			vec: new_vec!(None; size.min(1)),
+			head: 0,
+			tail: 0,
+			capacity: size,
+		}
+	}
+
+	/// Same as [CircularVec::new] but return error instead of aborting when allocation fail
+	pub fn try_new(size: usize) -> Result<Self, TryReserveError> {
+		let mut vec = Vec::new();
+		vec.try_reserve_exact(size)?;
+		vec.resize_with(size, || None);
+		Ok(Self { vec, head: 0, tail: 0, capacity: size })
+	}
+
 	#[inline]
 	fn advance_head(&mut self) {
 		let cap = self.vec.len();
@@ -44,10 +68,23 @@ impl<T> CircularVec<T> {
 		}
 	}
 
+	/// reallocate slots of lazy vec in logical order; allocated slots must be full
+	fn grow(&mut self) {
+		let items = self.take();
+		let len = items.len();
+		self.vec = new_vec!(None; len.saturating_mul(2).min(self.capacity));
+		for (slot, item) in self.vec.iter_mut().zip(items) { *slot = Some(item); }
+		self.head = len;
+		self.tail = 0;
+	}
+
 	/// Append value to ends of vec; if vec is full it will return oldest element
 	pub fn push(&mut self, item: T) -> Option<T> {
+		if self.vec.len() < self.capacity && self.head == self.tail && self.vec[self.tail].is_some() {
+			self.grow();
+		}
 		let head = self.head;
-		let last = mem::replace(&mut self.vec[head], Some(item));
+		let last = self.vec[head].replace(item);
 
 		// if head == tail can be empty or full  
 		// if last element is present vec is full otherwise empty
@@ -73,18 +110,46 @@ impl<T> CircularVec<T> {
 		mem::take(&mut self.vec[tail])
 	}
 
+	/// Try to remove newest element from vec
+	pub fn pop_back(&mut self) -> Option<T> {
+		if self.is_empty() {
+			return None;
+		}
+		self.head = if self.head == 0 { self.vec.len() - 1 } else { self.head - 1 };
+		mem::take(&mut self.vec[self.head])
+	}
+
+	/// Get newest element
+	pub fn last(&self) -> Option<&T> {
+		if self.is_empty() {
+			return None;
+		}
+		let head = if self.head == 0 { self.vec.len() - 1 } else { self.head - 1 };
+		self.vec[head].as_ref()
+	}
+
 	/// Get length of this circular vec
 	pub fn len(&self) -> usize {
 		let head = self.head;
 		let tail = self.tail;
 		if tail < head {
 			head - tail
-		} else {
+		} else if head < tail {
+			// head was wrapped around
+			self.vec.len() - tail + head
+		} else if self.vec[tail].is_some() {
 			// head == tail; so this vec is full
 			self.vec.len()
+		} else {
+			0
 		}
 	}
 
+	/// Get maximum number of elements this circular vec can hold
+	pub fn capacity(&self) -> usize {
+		self.capacity
+	}
+
 	/// Check if this circular vec is empty
 	pub fn is_empty(&self) -> bool {
 		self.vec[self.tail].is_none()
@@ -94,7 +159,7 @@ impl<T> CircularVec<T> {
 	pub fn is_full(&self) -> bool {
 		let head = self.head;
 		let tail = self.tail;
-		head == tail && self.vec[tail].is_some()
+		head == tail && self.vec[tail].is_some() && self.vec.len() == self.capacity
 	}
 
 	/// Take all data from this circular into vec
@@ -108,7 +173,7 @@ impl<T> CircularVec<T> {
 		out_vec
 	}
 
-	pub fn iter(&self) -> CircularVecIter<T> {
+	pub fn iter(&self) -> CircularVecIter<'_, T> {
 		CircularVecIter(self, 0)
 	}
 }
@@ -130,7 +195,7 @@ impl<'a, T> Iterator for CircularVecIter<'a, T> {
 	}
 }
 
-#[cfg(test)]
+#[cfg(all(test, feature = "std"))]
 mod test {
 	use crate::collection::CircularVec;
 
@@ -151,5 +216,31 @@ mod test {
 		assert_eq!(Some(5), vec.pop());
 		assert_eq!(Some(6), vec.pop());
 		assert_eq!(None, vec.pop());
+		assert_eq!(0, vec.len());
+		for i in 0..6 { vec.push(i); }
+		vec.pop();
+		assert_eq!(3, vec.len());
+		assert_eq!(vec![&3, &4, &5], vec.iter().collect::<Vec<&i32>>());
+		vec.push(6);
+		vec.push(7);
+		assert_eq!(Some(&7), vec.last());
+		assert_eq!(Some(7), vec.pop_back());
+		assert_eq!(Some(6), vec.pop_back());
+		assert_eq!(Some(4), vec.pop());
+		assert_eq!(Some(5), vec.pop_back());
+		assert_eq!(None, vec.pop_back());
+		assert!(vec.is_empty());
+		assert!(CircularVec::<u8>::try_new(usize::MAX).is_err());
+
+		// slots are allocated on demand
+		let mut vec = CircularVec::lazy(usize::MAX);
+		for i in 0..5 { vec.push(i); }
+		assert_eq!(vec.vec.len(), 8);
+		assert_eq!(vec.capacity(), usize::MAX);
+		assert_eq!(vec![&0, &1, &2, &3, &4], vec.iter().collect::<Vec<_>>());
+		let mut vec = CircularVec::lazy(3);
+		for i in 0..5 { vec.push(i); }
+		assert!(vec.is_full());
+		assert_eq!(vec![&2, &3, &4], vec.iter().collect::<Vec<_>>());
 	}
 }
\ No newline at end of file
@@ -0,0 +1,322 @@
+use std::ops::{Index, IndexMut};
+
+/// 2D collection stored in row-major contiguous vec
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::Grid;
+/// let grid = Grid::new(3, 2, |x, y| x + y * 10);
+/// assert_eq!(grid[(2, 1)], 12);
+/// assert_eq!(grid.row(1), &[10, 11, 12]);
+/// assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), vec![1, 11]);
+/// let rotated = grid.rotate_cw();
+/// assert_eq!(rotated.row(0), &[10, 0]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+	data: Vec<T>,
+	width: usize,
+	height: usize,
+}
+
+/// Offsets of 4-connected neighbors
+const NEIGHBORS4: [(isize, isize); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
+/// Offsets of 8-connected neighbors
+const NEIGHBORS8: [(isize, isize); 8] = [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)];
+
+impl<T> Grid<T> {
+	/// Create new grid and use result from `init(x, y)` as value of each cell
+	pub fn new(width: usize, height: usize, mut init: impl FnMut(usize, usize) -> T) -> Self {
+		let mut data = Vec::with_capacity(width * height);
+		for y in 0..height {
+			for x in 0..width { data.push(init(x, y)); }
+		}
+		Self { data, width, height }
+	}
+
+	/// Create grid from row-major vec; return None if length of vec is not `width * height`
+	pub fn from_vec(width: usize, height: usize, data: Vec<T>) -> Option<Self> {
+		if width.checked_mul(height)? != data.len() {
+			return None;
+		}
+		Some(Self { data, width, height })
+	}
+
+	/// Get row-major vec of this grid
+	pub fn into_vec(self) -> Vec<T> {
+		self.data
+	}
+
+	/// Get row-major slice of this grid
+	pub fn as_slice(&self) -> &[T] {
+		&self.data
+	}
+
+	/// Get mutable row-major slice of this grid
+	pub fn as_mut_slice(&mut self) -> &mut [T] {
+		&mut self.data
+	}
+
+	/// Get number of columns
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	/// Get number of rows
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	/// Get cell at given position
+	pub fn get(&self, x: usize, y: usize) -> Option<&T> {
+		if x < self.width && y < self.height { self.data.get(y * self.width + x) } else { None }
+	}
+
+	/// Get mutable cell at given position
+	pub fn get_mut(&mut self, x: usize, y: usize) -> Option<&mut T> {
+		if x < self.width && y < self.height { self.data.get_mut(y * self.width + x) } else { None }
+	}
+
+	/// Get row at given index
+	pub fn row(&self, y: usize) -> &[T] {
+		&self.data[y * self.width..(y + 1) * self.width]
+	}
+
+	/// Get mutable row at given index
+	pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+		&mut self.data[y * self.width..(y + 1) * self.width]
+	}
+
+	/// Iterate rows from top to bottom
+	pub fn rows(&self) -> impl DoubleEndedIterator<Item=&[T]> {
+		(0..self.height).map(|y| self.row(y))
+	}
+
+	/// Iterate cells in given column from top to bottom
+	pub fn column(&self, x: usize) -> impl Iterator<Item=&T> {
+		assert!(x < self.width);
+		self.data[x..].iter().step_by(self.width)
+	}
+
+	/// Iterate columns from left to right
+	pub fn columns(&self) -> impl Iterator<Item=impl Iterator<Item=&T>> {
+		(0..self.width).map(|x| self.column(x))
+	}
+
+	/// Iterate every cell with its position in row-major order
+	pub fn cells(&self) -> impl Iterator<Item=((usize, usize), &T)> {
+		let width = self.width;
+		self.data.iter().enumerate().map(move |(i, it)| ((i % width, i / width), it))
+	}
+
+	/// Get read-only view of rectangle inside this grid
+	pub fn view(&self, x: usize, y: usize, width: usize, height: usize) -> GridView<'_, T> {
+		assert!(x + width <= self.width && y + height <= self.height);
+		GridView { grid: self, x, y, width, height }
+	}
+
+	#[inline]
+	fn offset(&self, x: usize, y: usize, (dx, dy): (isize, isize)) -> Option<(usize, usize)> {
+		let nx = x.checked_add_signed(dx)?;
+		let ny = y.checked_add_signed(dy)?;
+		if nx < self.width && ny < self.height { Some((nx, ny)) } else { None }
+	}
+
+	/// Iterate positions of 4-connected neighbors (up, left, right, down)
+	pub fn neighbors4(&self, x: usize, y: usize) -> impl Iterator<Item=(usize, usize)> + '_ {
+		NEIGHBORS4.iter().filter_map(move |it| self.offset(x, y, *it))
+	}
+
+	/// Iterate positions of 8-connected neighbors in row-major order
+	pub fn neighbors8(&self, x: usize, y: usize) -> impl Iterator<Item=(usize, usize)> + '_ {
+		NEIGHBORS8.iter().filter_map(move |it| self.offset(x, y, *it))
+	}
+
+	/// Create new grid by applying `f` to every cell
+	pub fn map<U>(self, f: impl FnMut(T) -> U) -> Grid<U> {
+		Grid {
+			data: self.data.into_iter().map(f).collect(),
+			width: self.width,
+			height: self.height,
+		}
+	}
+}
+
+impl<T: Clone> Grid<T> {
+	/// Create new grid with swapped rows and columns
+	pub fn transpose(&self) -> Self {
+		Grid::new(self.height, self.width, |x, y| self[(y, x)].clone())
+	}
+
+	/// Create new grid rotated 90 degrees clockwise
+	pub fn rotate_cw(&self) -> Self {
+		let height = self.height;
+		Grid::new(self.height, self.width, |x, y| self[(y, height - 1 - x)].clone())
+	}
+
+	/// Create new grid rotated 90 degrees counterclockwise
+	pub fn rotate_ccw(&self) -> Self {
+		let width = self.width;
+		Grid::new(self.height, self.width, |x, y| self[(width - 1 - y, x)].clone())
+	}
+
+	/// Create new grid rotated 180 degrees
+	pub fn rotate_180(&self) -> Self {
+		let mut data = self.data.clone();
+		data.reverse();
+		Self { data, width: self.width, height: self.height }
+	}
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+	type Output = T;
+
+	fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+		self.get(x, y).expect("position out of bounds")
+	}
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+	fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+		self.get_mut(x, y).expect("position out of bounds")
+	}
+}
+
+/// Read-only rectangle inside [Grid]
+#[derive(Clone, Copy)]
+pub struct GridView<'a, T> {
+	grid: &'a Grid<T>,
+	x: usize,
+	y: usize,
+	width: usize,
+	height: usize,
+}
+
+impl<'a, T> GridView<'a, T> {
+	/// Get number of columns
+	pub fn width(&self) -> usize {
+		self.width
+	}
+
+	/// Get number of rows
+	pub fn height(&self) -> usize {
+		self.height
+	}
+
+	/// Get cell at given position (relative to this view)
+	pub fn get(&self, x: usize, y: usize) -> Option<&'a T> {
+		if x < self.width && y < self.height { self.grid.get(self.x + x, self.y + y) } else { None }
+	}
+
+	/// Get row at given index (relative to this view)
+	pub fn row(&self, y: usize) -> &'a [T] {
+		assert!(y < self.height);
+		&self.grid.row(self.y + y)[self.x..self.x + self.width]
+	}
+
+	/// Iterate rows from top to bottom
+	pub fn rows(&self) -> impl Iterator<Item=&'a [T]> + '_ {
+		(0..self.height).map(|y| self.row(y))
+	}
+
+	/// Copy this view into new grid
+	pub fn to_grid(&self) -> Grid<T> where T: Clone {
+		Grid::new(self.width, self.height, |x, y| self.row(y)[x].clone())
+	}
+}
+
+/// return error if dimension in header doesn't match size of pixel data
+#[cfg(feature = "mini-bmp")]
+impl TryFrom<&crate::mini_bmp::BitMap> for Grid<crate::mini_bmp::BGRA> {
+	type Error = &'static str;
+
+	fn try_from(value: &crate::mini_bmp::BitMap) -> Result<Self, Self::Error> {
+		let width = value.width() as usize;
+		let height = value.height() as usize;
+		let size = width.checked_mul(height).and_then(|it| it.checked_mul(4)).and_then(|it| it.checked_add(54));
+		if size.is_none_or(|it| it > value.len()) {
+			return Err("bitmap dimension exceed pixel data");
+		}
+		let pixels = value.pixels();
+		if value.is_top_down() {
+			Ok(Grid::new(width, height, |x, y| pixels[y * width + x]))
+		} else {
+			// bitmap rows are stored from bottom to top
+			Ok(Grid::new(width, height, |x, y| pixels[(height - 1 - y) * width + x]))
+		}
+	}
+}
+
+/// return error if grid is empty or too large for bitmap
+#[cfg(feature = "mini-bmp")]
+impl TryFrom<&Grid<crate::mini_bmp::BGRA>> for crate::mini_bmp::BitMap {
+	type Error = &'static str;
+
+	fn try_from(value: &Grid<crate::mini_bmp::BGRA>) -> Result<Self, Self::Error> {
+		if value.data.is_empty() {
+			return Err("bitmap can't be empty");
+		}
+		// file size is stored as u32
+		if value.data.len() > (u32::MAX as usize - 54) / 4 {
+			return Err("grid is too large for bitmap");
+		}
+		let mut bmp = crate::mini_bmp::BitMap::new(value.width() as i32, value.height() as i32);
+		let width = value.width();
+		for (row, pixels) in value.rows().rev().zip(bmp.pixels_mut().chunks_exact_mut(width)) {
+			pixels.copy_from_slice(row);
+		}
+		Ok(bmp)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::Grid;
+
+	#[test]
+	fn test_grid() {
+		// 0 1 2
+		// 3 4 5
+		let grid = Grid::new(3, 2, |x, y| x + y * 3);
+		assert_eq!(grid.rows().collect::<Vec<_>>(), vec![&[0, 1, 2], &[3, 4, 5]]);
+		assert_eq!(grid.columns().map(|it| it.copied().collect()).collect::<Vec<Vec<_>>>(), vec![vec![0, 3], vec![1, 4], vec![2, 5]]);
+		assert_eq!(grid.transpose().into_vec(), vec![0, 3, 1, 4, 2, 5]);
+		assert_eq!(grid.rotate_cw().into_vec(), vec![3, 0, 4, 1, 5, 2]);
+		assert_eq!(grid.rotate_ccw().into_vec(), vec![2, 5, 1, 4, 0, 3]);
+		assert_eq!(grid.rotate_180().into_vec(), vec![5, 4, 3, 2, 1, 0]);
+		assert_eq!(grid.neighbors4(0, 0).collect::<Vec<_>>(), vec![(1, 0), (0, 1)]);
+		assert_eq!(grid.neighbors8(1, 1).count(), 5);
+		let view = grid.view(1, 0, 2, 2);
+		assert_eq!(view.rows().collect::<Vec<_>>(), vec![&[1, 2], &[4, 5]]);
+		assert_eq!(view.to_grid().into_vec(), vec![1, 2, 4, 5]);
+		assert!(Grid::from_vec(2, 2, vec![0; 3]).is_none());
+	}
+
+	#[cfg(feature = "mini-bmp")]
+	#[test]
+	fn test_grid_bitmap() {
+		use crate::mini_bmp::{BGRA, BitMap};
+
+		let grid = Grid::new(2, 3, |x, y| BGRA { b: x as u8, g: y as u8, r: 0, a: 255 });
+		let bmp = BitMap::try_from(&grid).unwrap();
+		assert_eq!((bmp.width(), bmp.height()), (2, 3));
+		// bottom-left pixel is stored first
+		assert_eq!(bmp.pixels()[0], BGRA { b: 0, g: 2, r: 0, a: 255 });
+		assert_eq!(Grid::try_from(&bmp).unwrap(), grid);
+		assert!(BitMap::try_from(&Grid::new(0, 3, |_, _| BGRA::default())).is_err());
+
+		// negative height means rows are stored from top to bottom
+		let mut raw = bmp.to_vec();
+		raw[22..26].copy_from_slice(&(-3i32).to_le_bytes());
+		let top_down = BitMap::from_raw(&raw).unwrap();
+		assert!(top_down.is_top_down());
+		assert_eq!(top_down.height(), 3);
+		let flipped = Grid::try_from(&top_down).unwrap();
+		assert_eq!(flipped.row(0), grid.row(2));
+		assert_eq!(flipped.row(2), grid.row(0));
+
+		// header claim more pixels than data
+		raw[18..22].copy_from_slice(&u32::MAX.to_le_bytes());
+		assert!(BitMap::from_raw(&raw).is_none());
+		assert!(Grid::try_from(&unsafe { BitMap::from_vec(raw) }).is_err());
+	}
+}
@@ -0,0 +1,235 @@
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// number of children of each node
+const ARITY: usize = 4;
+
+struct Entry<K, P> {
+	key: K,
+	priority: P,
+	/// insertion order; used to break ties
+	seq: u64,
+}
+
+/// Min-heap (4-ary) that can change priority of or remove entry by its key in O(log n)
+/// + entries with equal priority are popped in FIFO order
+/// + use [std::cmp::Reverse] as priority for max-heap
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::IndexedHeap;
+/// let mut jobs = IndexedHeap::new();
+/// jobs.push("backup", 10);
+/// jobs.push("cleanup", 5);
+/// jobs.push("report", 5);
+/// jobs.change_priority("backup", 1);
+/// jobs.remove("cleanup");
+/// assert_eq!(jobs.pop(), Some(("backup", 1)));
+/// assert_eq!(jobs.pop(), Some(("report", 5)));
+/// ```
+pub struct IndexedHeap<K, P> {
+	heap: Vec<Entry<K, P>>,
+	/// position of key in `heap`
+	index: HashMap<K, usize>,
+	seq: u64,
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> Default for IndexedHeap<K, P> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<K: Hash + Eq + Clone, P: Ord> IndexedHeap<K, P> {
+	/// Create new empty heap
+	pub fn new() -> Self {
+		Self::with_capacity(0)
+	}
+
+	/// Create new heap that can hold `capacity` entries without reallocation
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self {
+			heap: Vec::with_capacity(capacity),
+			index: HashMap::with_capacity(capacity),
+			seq: 0,
+		}
+	}
+
+	/// Get number of entries
+	pub fn len(&self) -> usize {
+		self.heap.len()
+	}
+
+	/// Check if there is no entry
+	pub fn is_empty(&self) -> bool {
+		self.heap.is_empty()
+	}
+
+	/// Insert entry; if `key` already exists, its priority will be changed and old priority will be returned
+	pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+		if let Some(&pos) = self.index.get(&key) {
+			return Some(self.update(pos, priority));
+		}
+		let seq = self.next_seq();
+		self.index.insert(key.clone(), self.heap.len());
+		self.heap.push(Entry { key, priority, seq });
+		self.sift_up(self.heap.len() - 1);
+		None
+	}
+
+	/// Get entry with lowest priority
+	pub fn peek(&self) -> Option<(&K, &P)> {
+		self.heap.first().map(|it| (&it.key, &it.priority))
+	}
+
+	/// Remove entry with lowest priority
+	pub fn pop(&mut self) -> Option<(K, P)> {
+		if self.heap.is_empty() {
+			return None;
+		}
+		let entry = self.remove_at(0);
+		Some((entry.key, entry.priority))
+	}
+
+	/// Change priority of `key`; return old priority or None if `key` doesn't exist
+	/// updated entry will be placed after other entries with same priority
+	pub fn change_priority<Q>(&mut self, key: &Q, priority: P) -> Option<P>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let pos = *self.index.get(key)?;
+		Some(self.update(pos, priority))
+	}
+
+	/// Remove entry of `key` and return its priority
+	pub fn remove<Q>(&mut self, key: &Q) -> Option<P>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		let pos = *self.index.get(key)?;
+		Some(self.remove_at(pos).priority)
+	}
+
+	/// Get priority of `key`
+	pub fn priority<Q>(&self, key: &Q) -> Option<&P>
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.index.get(key).map(|it| &self.heap[*it].priority)
+	}
+
+	/// Check if heap contains `key`
+	pub fn contains_key<Q>(&self, key: &Q) -> bool
+		where K: Borrow<Q>,
+		      Q: Hash + Eq + ?Sized {
+		self.index.contains_key(key)
+	}
+
+	/// Iterate entries in arbitrary order
+	pub fn iter(&self) -> impl Iterator<Item=(&K, &P)> {
+		self.heap.iter().map(|it| (&it.key, &it.priority))
+	}
+
+	/// Iterate entries in the order they would be popped; O(n log n)
+	pub fn iter_sorted(&self) -> impl Iterator<Item=(&K, &P)> {
+		let mut entries = self.heap.iter().collect::<Vec<_>>();
+		entries.sort_unstable_by(|a, b| (&a.priority, a.seq).cmp(&(&b.priority, b.seq)));
+		entries.into_iter().map(|it| (&it.key, &it.priority))
+	}
+
+	/// Remove all entries
+	pub fn clear(&mut self) {
+		self.heap.clear();
+		self.index.clear();
+	}
+
+	fn next_seq(&mut self) -> u64 {
+		self.seq += 1;
+		self.seq
+	}
+
+	fn update(&mut self, pos: usize, priority: P) -> P {
+		let seq = self.next_seq();
+		let entry = &mut self.heap[pos];
+		entry.seq = seq;
+		let old = std::mem::replace(&mut entry.priority, priority);
+		self.sift_up(pos);
+		self.sift_down(pos);
+		old
+	}
+
+	fn remove_at(&mut self, pos: usize) -> Entry<K, P> {
+		let entry = self.heap.swap_remove(pos);
+		self.index.remove(&entry.key);
+		if pos < self.heap.len() {
+			self.index.insert(self.heap[pos].key.clone(), pos);
+			self.sift_up(pos);
+			self.sift_down(pos);
+		}
+		entry
+	}
+
+	#[inline]
+	fn less(&self, a: usize, b: usize) -> bool {
+		let (a, b) = (&self.heap[a], &self.heap[b]);
+		(&a.priority, a.seq) < (&b.priority, b.seq)
+	}
+
+	fn swap(&mut self, a: usize, b: usize) {
+		self.heap.swap(a, b);
+		*self.index.get_mut(&self.heap[a].key).unwrap() = a;
+		*self.index.get_mut(&self.heap[b].key).unwrap() = b;
+	}
+
+	fn sift_up(&mut self, mut pos: usize) {
+		while pos > 0 {
+			let parent = (pos - 1) / ARITY;
+			if !self.less(pos, parent) { break; }
+			self.swap(pos, parent);
+			pos = parent;
+		}
+	}
+
+	fn sift_down(&mut self, mut pos: usize) {
+		loop {
+			let first = pos * ARITY + 1;
+			let last = (first + ARITY).min(self.heap.len());
+			let Some(min) = (first..last).reduce(|a, b| if self.less(b, a) { b } else { a }) else { break; };
+			if !self.less(min, pos) { break; }
+			self.swap(pos, min);
+			pos = min;
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::IndexedHeap;
+
+	#[test]
+	fn test_indexed_heap() {
+		let mut heap = IndexedHeap::new();
+		for i in 0..100u32 {
+			heap.push(i, i.wrapping_mul(2654435761) % 1000);
+		}
+		for i in (0..100).step_by(3) {
+			heap.remove(&i);
+		}
+		for i in (1..100).step_by(3) {
+			heap.change_priority(&i, 0);
+		}
+		let mut prev = None;
+		let mut zeros = Vec::new();
+		while let Some((key, priority)) = heap.pop() {
+			assert!(prev <= Some(priority));
+			if priority == 0 { zeros.push(key); }
+			prev = Some(priority);
+		}
+		// FIFO order of equal priorities
+		assert_eq!(zeros, (1..100).step_by(3).collect::<Vec<_>>());
+		assert!(heap.is_empty());
+
+		let mut heap = IndexedHeap::new();
+		assert_eq!(heap.push("a", 1), None);
+		assert_eq!(heap.push("a", 2), Some(1));
+		assert_eq!(heap.priority("a"), Some(&2));
+		assert_eq!(heap.change_priority("b", 0), None);
+	}
+}
@@ -0,0 +1,260 @@
+use std::collections::BTreeMap;
+use std::fmt::{Debug, Formatter};
+use std::ops::Range;
+
+/// Map from half-open ranges to values; stored ranges never overlap
+/// + inserting range overwrite overlapped part of existing ranges (existing ranges are split)
+/// + adjacent ranges with equal value are merged
+/// + overlapping intervals are stored by using collection as value and [IntervalMap::update_range]
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::IntervalMap;
+/// // written byte ranges of file
+/// let mut written = IntervalMap::new();
+/// written.insert(0..100, ());
+/// written.insert(200..300, ());
+/// written.insert(100..150, ());
+/// assert_eq!(written.iter().map(|(range, _)| range).collect::<Vec<_>>(), vec![0..150, 200..300]);
+/// assert_eq!(written.gaps(0..400).collect::<Vec<_>>(), vec![150..200, 300..400]);
+/// ```
+#[derive(Clone, PartialEq, Eq)]
+pub struct IntervalMap<K, V> {
+	/// start => (end, value)
+	map: BTreeMap<K, (K, V)>,
+}
+
+impl<K, V> Default for IntervalMap<K, V> {
+	fn default() -> Self {
+		Self { map: BTreeMap::new() }
+	}
+}
+
+impl<K: Ord + Clone, V> IntervalMap<K, V> {
+	/// Create new empty interval map
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Get number of stored ranges
+	pub fn len(&self) -> usize {
+		self.map.len()
+	}
+
+	/// Check if there is no range
+	pub fn is_empty(&self) -> bool {
+		self.map.is_empty()
+	}
+
+	/// Remove all ranges
+	pub fn clear(&mut self) {
+		self.map.clear();
+	}
+
+	/// Get range that contains `key` and its value
+	pub fn get_key_value(&self, key: &K) -> Option<(Range<K>, &V)> {
+		let (start, (end, value)) = self.map.range(..=key).next_back()?;
+		if end > key { Some((start.clone()..end.clone(), value)) } else { None }
+	}
+
+	/// Get value of range that contains `key`
+	pub fn get(&self, key: &K) -> Option<&V> {
+		self.get_key_value(key).map(|(_, it)| it)
+	}
+
+	/// Check if `key` is covered by any range
+	pub fn contains(&self, key: &K) -> bool {
+		self.get_key_value(key).is_some()
+	}
+
+	/// Iterate ranges in ascending order
+	pub fn iter(&self) -> impl Iterator<Item=(Range<K>, &V)> {
+		self.map.iter().map(|(start, (end, value))| (start.clone()..end.clone(), value))
+	}
+
+	/// Iterate ranges that overlap with `range` in ascending order; ranges are not clipped
+	pub fn overlapping(&self, range: Range<K>) -> impl Iterator<Item=(Range<K>, &V)> {
+		let first = if range.is_empty() { None } else { self.map.range(..&range.start).next_back() }
+			.filter(|(_, (end, _))| *end > range.start);
+		let rest = if range.is_empty() { None } else { Some(self.map.range(range.start..range.end)) };
+		first.into_iter().chain(rest.into_iter().flatten())
+			.map(|(start, (end, value))| (start.clone()..end.clone(), value))
+	}
+
+	/// Iterate parts of `range` that are not covered by any range
+	pub fn gaps(&self, range: Range<K>) -> impl Iterator<Item=Range<K>> + '_ {
+		let mut cursor = range.start.clone();
+		let end = range.end.clone();
+		let mut ranges = self.overlapping(range);
+		let mut done = false;
+		std::iter::from_fn(move || {
+			while !done {
+				match ranges.next() {
+					Some((it, _)) => {
+						let gap = cursor.clone()..it.start.clone();
+						if it.end > cursor { cursor = it.end; }
+						if !gap.is_empty() { return Some(gap); }
+					}
+					None => {
+						done = true;
+						let gap = cursor.clone()..end.clone();
+						if !gap.is_empty() { return Some(gap); }
+					}
+				}
+			}
+			None
+		})
+	}
+}
+
+impl<K: Ord + Clone, V: Clone> IntervalMap<K, V> {
+	/// Remove `range`; ranges that partially overlap with `range` will be shrunk or split
+	pub fn remove(&mut self, range: Range<K>) {
+		if range.is_empty() {
+			return;
+		}
+		// range that start before `range`
+		if let Some((start, (end, _))) = self.map.range(..&range.start).next_back() {
+			if *end > range.start {
+				let start = start.clone();
+				let (end, value) = self.map.remove(&start).unwrap();
+				if end > range.end {
+					self.map.insert(range.end.clone(), (end, value.clone()));
+				}
+				self.map.insert(start, (range.start.clone(), value));
+			}
+		}
+		let inside = self.map.range(range.start.clone()..range.end.clone()).map(|(it, _)| it.clone()).collect::<Vec<_>>();
+		for start in inside {
+			let (end, value) = self.map.remove(&start).unwrap();
+			if end > range.end {
+				self.map.insert(range.end.clone(), (end, value));
+			}
+		}
+	}
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> IntervalMap<K, V> {
+	/// Insert range; overlapped part of existing ranges will be replaced
+	pub fn insert(&mut self, range: Range<K>, value: V) {
+		if range.is_empty() {
+			return;
+		}
+		self.remove(range.clone());
+		let Range { mut start, mut end } = range;
+		// merge with previous range
+		if let Some((prev, (prev_end, prev_value))) = self.map.range(..&start).next_back() {
+			if *prev_end == start && *prev_value == value {
+				start = prev.clone();
+			}
+		}
+		// merge with next range
+		if let Some((next_end, next_value)) = self.map.get(&end) {
+			if *next_value == value {
+				let next_end = next_end.clone();
+				self.map.remove(&end);
+				end = next_end;
+			}
+		}
+		self.map.insert(start, (end, value));
+	}
+
+	/// Replace value of every part of `range` with `f(current)`, where gaps are `None`;
+	/// returning `None` remove that part  
+	/// ranges are split at boundaries of `range` and adjacent equal values are merged afterward
+	/// # Example
+	/// ```rust
+	/// use std::collections::BTreeSet;
+	/// use pedestal_rs::collection::IntervalMap;
+	/// // overlapping intervals; each segment hold every interval covering it
+	/// let mut events = IntervalMap::<u32, BTreeSet<&str>>::new();
+	/// for (range, name) in [(0..10, "a"), (5..15, "b"), (8..9, "c")] {
+	///     events.update_range(range, |it| {
+	///         let mut names = it.cloned().unwrap_or_default();
+	///         names.insert(name);
+	///         Some(names)
+	///     });
+	/// }
+	/// assert_eq!(events.get(&8), Some(&BTreeSet::from(["a", "b", "c"])));
+	/// assert_eq!(events.get(&12), Some(&BTreeSet::from(["b"])));
+	/// // remove "b" from every interval
+	/// events.update_range(0..15, |it| Some(it?.iter().copied().filter(|it| *it != "b").collect::<BTreeSet<_>>()).filter(|it| !it.is_empty()));
+	/// assert_eq!(events.iter().map(|(range, _)| range).collect::<Vec<_>>(), vec![0..8, 8..9, 9..10]);
+	/// ```
+	pub fn update_range(&mut self, range: Range<K>, mut f: impl FnMut(Option<&V>) -> Option<V>) {
+		if range.is_empty() {
+			return;
+		}
+		let mut parts = Vec::new();
+		let mut cursor = range.start.clone();
+		for (it, value) in self.overlapping(range.clone()) {
+			let start = it.start.max(range.start.clone());
+			let end = it.end.min(range.end.clone());
+			if cursor < start {
+				parts.push((cursor..start.clone(), f(None)));
+			}
+			parts.push((start..end.clone(), f(Some(value))));
+			cursor = end;
+		}
+		if cursor < range.end {
+			parts.push((cursor..range.end.clone(), f(None)));
+		}
+		self.remove(range);
+		for (range, value) in parts {
+			if let Some(value) = value { self.insert(range, value); }
+		}
+	}
+}
+
+impl<K: Debug, V: Debug> Debug for IntervalMap<K, V> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_map().entries(self.map.iter().map(|(start, (end, value))| (start..end, value))).finish()
+	}
+}
+
+impl<K: Ord + Clone, V: Clone + PartialEq> FromIterator<(Range<K>, V)> for IntervalMap<K, V> {
+	fn from_iter<I: IntoIterator<Item=(Range<K>, V)>>(iter: I) -> Self {
+		let mut map = Self::new();
+		for (range, value) in iter { map.insert(range, value); }
+		map
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::IntervalMap;
+
+	#[test]
+	fn test_interval_map() {
+		let mut map = IntervalMap::new();
+		map.insert(0..10, 'a');
+		map.insert(20..30, 'b');
+		// split 'a' and overwrite start of 'b'
+		map.insert(5..25, 'c');
+		assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..5, &'a'), (5..25, &'c'), (25..30, &'b')]);
+		assert_eq!(map.get(&4), Some(&'a'));
+		assert_eq!(map.get(&25), Some(&'b'));
+		assert_eq!(map.get(&30), None);
+		map.insert(25..27, 'c');
+		assert_eq!(map.get_key_value(&10), Some((5..27, &'c')));
+		map.remove(2..8);
+		assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..2, &'a'), (8..27, &'c'), (27..30, &'b')]);
+		assert_eq!(map.overlapping(1..9).map(|(it, _)| it).collect::<Vec<_>>(), vec![0..2, 8..27]);
+		assert_eq!(map.gaps(0..40).collect::<Vec<_>>(), vec![2..8, 30..40]);
+		assert_eq!(map.gaps(10..20).count(), 0);
+		map.remove(10..12);
+		assert_eq!(map.len(), 4);
+		// update clipped parts and gaps, then merge equal neighbors
+		let mut map = [(0..4, 1), (6..10, 2)].into_iter().collect::<IntervalMap<_, _>>();
+		let mut seen = Vec::new();
+		map.update_range(2..8, |it| {
+			seen.push(it.copied());
+			Some(it.map_or(0, |it| it + 1))
+		});
+		assert_eq!(seen, vec![Some(1), None, Some(2)]);
+		assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..2, &1), (2..4, &2), (4..6, &0), (6..8, &3), (8..10, &2)]);
+		map.update_range(0..6, |it| it.filter(|it| **it != 2).copied());
+		assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..2, &1), (4..6, &0), (6..8, &3), (8..10, &2)]);
+		map.update_range(5..10, |_| Some(0));
+		assert_eq!(map.iter().collect::<Vec<_>>(), vec![(0..2, &1), (4..10, &0)]);
+	}
+}
@@ -0,0 +1,225 @@
+use std::borrow::Cow;
+
+use crate::collection::CircularVec;
+
+/// default value of [LineRing::with_max_line_len]
+const DEFAULT_MAX_LINE_LEN: usize = 64 * 1024;
+
+/// Split stream of bytes into lines and keep only latest lines  
+/// + `\n` and `\r\n` end current line
+/// + bare `\r` (eg. progress bar) will replace current line with following text
+/// + line longer than max line length will be split into multiple lines
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::LineRing;
+/// let mut lines = LineRing::new(2);
+/// lines.push(b"first\r\nsec");
+/// lines.push(b"ond\nprogress 10%\rprogress 100%");
+/// lines.finish();
+/// assert_eq!(lines.lines().collect::<Vec<_>>(), vec!["second", "progress 100%"]);
+/// ```
+pub struct LineRing {
+	lines: CircularVec<Vec<u8>>,
+	/// current incomplete line
+	partial: Vec<u8>,
+	/// previous chunk was ended with `\r`
+	pending_cr: bool,
+	/// total bytes of completed lines
+	bytes: usize,
+	max_bytes: Option<usize>,
+	max_line_len: usize,
+}
+
+impl LineRing {
+	/// Create new line ring that keep latest `max_lines` lines
+	pub fn new(max_lines: usize) -> Self {
+		Self {
+			lines: CircularVec::new(max_lines),
+			partial: Vec::new(),
+			pending_cr: false,
+			bytes: 0,
+			max_bytes: None,
+			max_line_len: DEFAULT_MAX_LINE_LEN,
+		}
+	}
+
+	/// Limit total bytes of stored lines; oldest lines will be discarded when exceeded
+	#[must_use]
+	pub fn with_max_bytes(mut self, max_bytes: usize) -> Self {
+		self.max_bytes = Some(max_bytes);
+		self
+	}
+
+	/// Limit length of single line (default 64 KiB); longer line will be split
+	#[must_use]
+	pub fn with_max_line_len(mut self, max_line_len: usize) -> Self {
+		assert!(max_line_len > 0);
+		self.max_line_len = max_line_len;
+		self
+	}
+
+	/// Append chunk of bytes; chunk doesn't need to end at line boundary
+	pub fn push(&mut self, mut chunk: &[u8]) {
+		while !chunk.is_empty() {
+			if self.pending_cr {
+				self.pending_cr = false;
+				match chunk[0] {
+					b'\n' => {
+						self.complete();
+						chunk = &chunk[1..];
+						continue;
+					}
+					// carriage return; following text will replace current line
+					_ => { self.partial.clear(); }
+				}
+			}
+			match chunk.iter().position(|it| *it == b'\n' || *it == b'\r') {
+				Some(pos) => {
+					self.extend_partial(&chunk[..pos]);
+					if chunk[pos] == b'\n' {
+						self.complete();
+					} else {
+						self.pending_cr = true;
+					}
+					chunk = &chunk[pos + 1..];
+				}
+				None => {
+					self.extend_partial(chunk);
+					break;
+				}
+			}
+		}
+	}
+
+	fn extend_partial(&mut self, mut data: &[u8]) {
+		while self.partial.len() + data.len() > self.max_line_len {
+			let (head, tail) = data.split_at(self.max_line_len - self.partial.len());
+			self.partial.extend_from_slice(head);
+			self.complete();
+			data = tail;
+		}
+		self.partial.extend_from_slice(data);
+	}
+
+	/// move partial line into completed lines
+	fn complete(&mut self) {
+		let line = std::mem::take(&mut self.partial);
+		self.bytes += line.len();
+		if let Some(old) = self.lines.push(line) {
+			self.bytes -= old.len();
+		}
+		if let Some(max_bytes) = self.max_bytes {
+			while self.bytes > max_bytes {
+				match self.lines.pop() {
+					Some(old) => { self.bytes -= old.len(); }
+					None => { break; }
+				}
+			}
+		}
+	}
+
+	/// End of stream; incomplete line will be treated as completed line
+	pub fn finish(&mut self) {
+		self.pending_cr = false;
+		if !self.partial.is_empty() {
+			self.complete();
+		}
+	}
+
+	/// Get number of completed lines
+	pub fn len(&self) -> usize {
+		self.lines.len()
+	}
+
+	/// Check if there is no completed line
+	pub fn is_empty(&self) -> bool {
+		self.lines.is_empty()
+	}
+
+	/// Get total bytes of completed lines (without line break)
+	pub fn bytes(&self) -> usize {
+		self.bytes
+	}
+
+	/// Iterate completed lines from oldest to newest (without line break)
+	pub fn iter(&self) -> impl Iterator<Item=&[u8]> {
+		self.lines.iter().map(|it| it.as_slice())
+	}
+
+	/// Iterate completed lines as lossy UTF-8 string
+	pub fn lines(&self) -> impl Iterator<Item=Cow<'_, str>> {
+		self.iter().map(String::from_utf8_lossy)
+	}
+
+	/// Get current incomplete line as lossy UTF-8 string
+	pub fn partial(&self) -> Cow<'_, str> {
+		String::from_utf8_lossy(&self.partial)
+	}
+
+	/// Take all completed lines
+	pub fn take(&mut self) -> Vec<Vec<u8>> {
+		self.bytes = 0;
+		self.lines.take()
+	}
+
+	/// Read from `reader` until EOF and call [LineRing::finish]
+	#[cfg(feature = "async")]
+	pub async fn feed_async<R: futures::io::AsyncRead + Unpin>(&mut self, reader: &mut R) -> std::io::Result<()> {
+		use futures::io::AsyncReadExt;
+		let mut buf = vec![0u8; 8192];
+		loop {
+			let len = reader.read(&mut buf).await?;
+			if len == 0 { break; }
+			self.push(&buf[..len]);
+		}
+		self.finish();
+		Ok(())
+	}
+
+	/// Read from tokio `reader` (eg. `ChildWrapper::stdout`) until EOF and call [LineRing::finish]
+	#[cfg(feature = "tokio")]
+	pub async fn feed_tokio<R: tokio::io::AsyncRead + Unpin>(&mut self, reader: &mut R) -> std::io::Result<()> {
+		use std::future::poll_fn;
+		use std::pin::Pin;
+		let mut buf = vec![0u8; 8192];
+		loop {
+			let mut read_buf = tokio::io::ReadBuf::new(&mut buf);
+			poll_fn(|cx| Pin::new(&mut *reader).poll_read(cx, &mut read_buf)).await?;
+			let len = read_buf.filled().len();
+			if len == 0 { break; }
+			self.push(&buf[..len]);
+		}
+		self.finish();
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::LineRing;
+
+	#[test]
+	fn test_line_ring() {
+		let mut lines = LineRing::new(4).with_max_line_len(4).with_max_bytes(8);
+		// \r\n across chunk boundary
+		lines.push(b"ab\r");
+		lines.push(b"\ncd\r");
+		lines.push(b"e\n123456");
+		assert_eq!(lines.lines().collect::<Vec<_>>(), vec!["ab", "e", "1234"]);
+		assert_eq!(lines.partial(), "56");
+		lines.push(b"\xff\n");
+		// "ab" was discarded because of byte limit
+		assert_eq!(lines.lines().collect::<Vec<_>>(), vec!["e", "1234", "56\u{FFFD}"]);
+		assert_eq!(lines.bytes(), 8);
+	}
+
+	#[cfg(feature = "tokio")]
+	#[test]
+	fn test_line_ring_feed() {
+		let mut lines = LineRing::new(4);
+		let mut input: &[u8] = b"hello\nworld";
+		tokio::runtime::Builder::new_current_thread().build().unwrap()
+			.block_on(lines.feed_tokio(&mut input)).unwrap();
+		assert_eq!(lines.take(), vec![b"hello".to_vec(), b"world".to_vec()]);
+	}
+}
@@ -0,0 +1,370 @@
+use std::ffi::c_void;
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::io::ErrorKind;
+use std::num::NonZeroUsize;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::sync::atomic::{fence, Ordering};
+
+use nix::fcntl::{flock, FlockArg};
+use nix::sys::mman::{MapFlags, mmap, MsFlags, msync, munmap, ProtFlags};
+
+/// "MRNG"
+const META_MAGIC: u32 = 0x474e_524d;
+const META_VERSION: u32 = 1;
+/// size of metadata region before ring data
+const HEADER_SIZE: usize = 4096;
+/// metadata is written alternately into 2 slots so torn write won't destroy both
+const META_SLOT_SIZE: usize = 64;
+/// length (u32) + checksum (u32)
+const RECORD_HEADER_SIZE: u64 = 8;
+
+/// Position of records inside ring; offsets are logical and never wrap
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Meta {
+	capacity: u64,
+	seq: u64,
+	head: u64,
+	tail: u64,
+}
+
+impl Meta {
+	fn encode(&self) -> [u8; 44] {
+		let mut buf = [0u8; 44];
+		buf[0..4].copy_from_slice(&META_MAGIC.to_le_bytes());
+		buf[4..8].copy_from_slice(&META_VERSION.to_le_bytes());
+		buf[8..16].copy_from_slice(&self.capacity.to_le_bytes());
+		buf[16..24].copy_from_slice(&self.seq.to_le_bytes());
+		buf[24..32].copy_from_slice(&self.head.to_le_bytes());
+		buf[32..40].copy_from_slice(&self.tail.to_le_bytes());
+		let checksum = crc32(&buf[..40]);
+		buf[40..44].copy_from_slice(&checksum.to_le_bytes());
+		buf
+	}
+
+	fn decode(buf: &[u8; 44]) -> Option<Self> {
+		let u32_at = |off: usize| u32::from_le_bytes(buf[off..off + 4].try_into().unwrap());
+		let u64_at = |off: usize| u64::from_le_bytes(buf[off..off + 8].try_into().unwrap());
+		if u32_at(0) != META_MAGIC || u32_at(4) != META_VERSION || u32_at(40) != crc32(&buf[..40]) {
+			return None;
+		}
+		let meta = Self {
+			capacity: u64_at(8),
+			seq: u64_at(16),
+			head: u64_at(24),
+			tail: u64_at(32),
+		};
+		if meta.tail > meta.head || meta.head - meta.tail > meta.capacity {
+			return None;
+		}
+		Some(meta)
+	}
+}
+
+/// Circular log of length-prefixed records stored in memory-mapped file  
+/// oldest records will be discarded when ring is full, content will survive process crash
+/// # Crash safety
+/// + head/tail are stored twice with checksum and sequence number; torn metadata will fallback to previous copy
+/// + every record has checksum; torn record will be truncated when ring is opened for writing
+/// + use [MmapRing::flush] to make sure data was written to disk (eg. power loss)
+/// # Concurrency
+/// only single writer is allowed (guarded by `flock`), any number of processes can read  
+/// with [MmapRing::open_read_only]
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::MmapRing;
+/// let path = std::env::temp_dir().join("pedestal_mmap_ring_doc.log");
+/// # let _ = std::fs::remove_file(&path);
+/// {
+///     let mut ring = MmapRing::open(&path, 64).unwrap();
+///     ring.push(b"hello").unwrap();
+///     ring.push(b"world").unwrap();
+/// }
+/// let ring = MmapRing::open_read_only(&path).unwrap();
+/// assert_eq!(ring.records().unwrap(), vec![b"hello".to_vec(), b"world".to_vec()]);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+pub struct MmapRing {
+	ptr: *mut u8,
+	len: usize,
+	capacity: u64,
+	writable: bool,
+	/// latest metadata written by this handle (writer only)
+	meta: Meta,
+	_file: File,
+}
+
+// raw pointer only point to our own mapping
+unsafe impl Send for MmapRing {}
+
+unsafe impl Sync for MmapRing {}
+
+impl MmapRing {
+	/// Open ring file for writing; if file doesn't exist it will be created with given data capacity  
+	/// return `ErrorKind::InvalidInput` if existing file has different capacity  
+	/// or `ErrorKind::WouldBlock` if another process is writing to this file
+	pub fn open(path: impl AsRef<Path>, capacity: usize) -> io::Result<Self> {
+		if (capacity as u64) <= RECORD_HEADER_SIZE {
+			return Err(ErrorKind::InvalidInput.into());
+		}
+		let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(path)?;
+		flock(file.as_raw_fd(), FlockArg::LockExclusiveNonblock).map_err(io::Error::from)?;
+		let len = HEADER_SIZE + capacity;
+		let existing = file.metadata()?.len();
+		if existing == 0 {
+			file.set_len(len as u64)?;
+		} else if existing != len as u64 {
+			return Err(ErrorKind::InvalidInput.into());
+		}
+		let mut ring = Self::map(file, len, true)?;
+		match ring.load_meta() {
+			Some(meta) if meta.capacity == capacity as u64 => {
+				ring.meta = meta;
+				ring.recover();
+			}
+			Some(_) => { return Err(ErrorKind::InvalidInput.into()); }
+			None => {
+				// new file or both metadata are broken
+				ring.meta = Meta { capacity: capacity as u64, seq: 0, head: 0, tail: 0 };
+				ring.commit(ring.meta.head, ring.meta.tail);
+			}
+		}
+		Ok(ring)
+	}
+
+	/// Open existing ring file for reading
+	pub fn open_read_only(path: impl AsRef<Path>) -> io::Result<Self> {
+		let file = File::open(path)?;
+		let len = file.metadata()?.len() as usize;
+		if len <= HEADER_SIZE {
+			return Err(ErrorKind::InvalidData.into());
+		}
+		let mut ring = Self::map(file, len, false)?;
+		let meta = ring.load_meta().ok_or(ErrorKind::InvalidData)?;
+		if meta.capacity != (len - HEADER_SIZE) as u64 {
+			return Err(ErrorKind::InvalidData.into());
+		}
+		ring.meta = meta;
+		Ok(ring)
+	}
+
+	fn map(file: File, len: usize, writable: bool) -> io::Result<Self> {
+		let prot = if writable { ProtFlags::PROT_READ | ProtFlags::PROT_WRITE } else { ProtFlags::PROT_READ };
+		let ptr = unsafe {
+			mmap(None, NonZeroUsize::new(len).unwrap(), prot, MapFlags::MAP_SHARED, file.as_raw_fd(), 0)
+		}.map_err(io::Error::from)?;
+		Ok(Self {
+			ptr: ptr.cast(),
+			len,
+			capacity: (len - HEADER_SIZE) as u64,
+			writable,
+			meta: Meta { capacity: 0, seq: 0, head: 0, tail: 0 },
+			_file: file,
+		})
+	}
+
+	/// Get data capacity in bytes (including 8 bytes header of each record)
+	pub fn capacity(&self) -> usize {
+		self.capacity as usize
+	}
+
+	/// Read latest valid metadata
+	fn load_meta(&self) -> Option<Meta> {
+		let read = |slot: usize| {
+			let mut buf = [0u8; 44];
+			unsafe { ptr::copy_nonoverlapping(self.ptr.add(slot * META_SLOT_SIZE), buf.as_mut_ptr(), buf.len()); }
+			Meta::decode(&buf)
+		};
+		fence(Ordering::Acquire);
+		match (read(0), read(1)) {
+			(Some(a), Some(b)) => Some(if a.seq > b.seq { a } else { b }),
+			(a, b) => a.or(b),
+		}
+	}
+
+	/// Write new metadata into older slot
+	fn commit(&mut self, head: u64, tail: u64) {
+		let meta = Meta { capacity: self.capacity, seq: self.meta.seq + 1, head, tail };
+		let buf = meta.encode();
+		fence(Ordering::Release);
+		unsafe {
+			ptr::copy_nonoverlapping(buf.as_ptr(), self.ptr.add((meta.seq % 2) as usize * META_SLOT_SIZE), buf.len());
+		}
+		self.meta = meta;
+	}
+
+	fn write_at(&mut self, offset: u64, data: &[u8]) {
+		let pos = (offset % self.capacity) as usize;
+		let first = data.len().min(self.capacity as usize - pos);
+		unsafe {
+			let base = self.ptr.add(HEADER_SIZE);
+			ptr::copy_nonoverlapping(data.as_ptr(), base.add(pos), first);
+			ptr::copy_nonoverlapping(data.as_ptr().add(first), base, data.len() - first);
+		}
+	}
+
+	fn read_at(&self, offset: u64, data: &mut [u8]) {
+		let pos = (offset % self.capacity) as usize;
+		let first = data.len().min(self.capacity as usize - pos);
+		unsafe {
+			let base = self.ptr.add(HEADER_SIZE);
+			ptr::copy_nonoverlapping(base.add(pos), data.as_mut_ptr(), first);
+			ptr::copy_nonoverlapping(base, data.as_mut_ptr().add(first), data.len() - first);
+		}
+	}
+
+	/// Read record at given offset; return None if record is broken or out of range
+	fn read_record(&self, offset: u64, head: u64) -> Option<Vec<u8>> {
+		let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+		if offset + RECORD_HEADER_SIZE > head { return None; }
+		self.read_at(offset, &mut header);
+		let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as u64;
+		let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+		if offset + RECORD_HEADER_SIZE + len > head { return None; }
+		let mut data = vec![0u8; len as usize];
+		self.read_at(offset + RECORD_HEADER_SIZE, &mut data);
+		if crc32(&data) != checksum { return None; }
+		Some(data)
+	}
+
+	/// Drop torn records at the end of ring
+	fn recover(&mut self) {
+		let Meta { head, tail, .. } = self.meta;
+		let mut offset = tail;
+		while offset < head {
+			match self.read_record(offset, head) {
+				Some(data) => { offset += RECORD_HEADER_SIZE + data.len() as u64; }
+				None => { break; }
+			}
+		}
+		if offset != head {
+			self.commit(offset, tail);
+		}
+	}
+
+	/// Append record to ring and discard oldest records if there is not enough space  
+	/// return `ErrorKind::InvalidInput` if record is larger than ring
+	pub fn push(&mut self, record: &[u8]) -> io::Result<()> {
+		if !self.writable {
+			return Err(ErrorKind::PermissionDenied.into());
+		}
+		let size = RECORD_HEADER_SIZE + record.len() as u64;
+		if size > self.capacity || record.len() > u32::MAX as usize {
+			return Err(ErrorKind::InvalidInput.into());
+		}
+		let Meta { head, mut tail, .. } = self.meta;
+		if head + size - tail > self.capacity {
+			while head + size - tail > self.capacity {
+				let mut len = [0u8; 4];
+				self.read_at(tail, &mut len);
+				tail += RECORD_HEADER_SIZE + u32::from_le_bytes(len) as u64;
+			}
+			// release space before overwrite it so readers won't see partially overwritten records
+			self.commit(head, tail);
+		}
+		let mut header = [0u8; RECORD_HEADER_SIZE as usize];
+		header[..4].copy_from_slice(&(record.len() as u32).to_le_bytes());
+		header[4..].copy_from_slice(&crc32(record).to_le_bytes());
+		self.write_at(head, &header);
+		self.write_at(head + RECORD_HEADER_SIZE, record);
+		self.commit(head + size, tail);
+		Ok(())
+	}
+
+	/// Copy all records from oldest to newest  
+	/// records that were overwritten by writer while reading will be skipped
+	pub fn records(&self) -> io::Result<Vec<Vec<u8>>> {
+		let Meta { head, tail, .. } = self.load_meta().ok_or(ErrorKind::InvalidData)?;
+		let mut records = Vec::new();
+		let mut offset = tail;
+		while offset < head {
+			match self.read_record(offset, head) {
+				Some(data) => {
+					let next = offset + RECORD_HEADER_SIZE + data.len() as u64;
+					records.push((offset, data));
+					offset = next;
+				}
+				None => { break; }
+			}
+		}
+		let Meta { tail, .. } = self.load_meta().ok_or(ErrorKind::InvalidData)?;
+		Ok(records.into_iter().filter(|(offset, _)| *offset >= tail).map(|(_, it)| it).collect())
+	}
+
+	/// Remove all records
+	pub fn clear(&mut self) -> io::Result<()> {
+		if !self.writable {
+			return Err(ErrorKind::PermissionDenied.into());
+		}
+		let head = self.meta.head;
+		self.commit(head, head);
+		Ok(())
+	}
+
+	/// Flush content of this ring to disk
+	pub fn flush(&self) -> io::Result<()> {
+		unsafe { msync(self.ptr.cast::<c_void>(), self.len, MsFlags::MS_SYNC) }.map_err(io::Error::from)
+	}
+}
+
+impl Drop for MmapRing {
+	fn drop(&mut self) {
+		let _ = unsafe { munmap(self.ptr.cast::<c_void>(), self.len) };
+	}
+}
+
+/// CRC-32 (IEEE)
+fn crc32(data: &[u8]) -> u32 {
+	let mut crc = !0u32;
+	for byte in data {
+		crc ^= *byte as u32;
+		for _ in 0..8 {
+			let mask = (crc & 1).wrapping_neg();
+			crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+		}
+	}
+	!crc
+}
+
+#[cfg(test)]
+mod test {
+	use std::fs::remove_file;
+
+	use crate::collection::MmapRing;
+	use crate::collection::mmap_ring::META_SLOT_SIZE;
+
+	#[test]
+	fn test_mmap_ring() {
+		let path = "target/test_mmap_ring.log";
+		let _ = remove_file(path);
+		{
+			let mut ring = MmapRing::open(path, 32).unwrap();
+			// another writer is not allowed
+			assert!(MmapRing::open(path, 32).is_err());
+			ring.push(b"first").unwrap();
+			ring.push(b"second").unwrap();
+			// 13 + 14 + 13 > 32; first will be discarded
+			ring.push(b"third").unwrap();
+			assert!(ring.push(&[0; 32]).is_err());
+			assert_eq!(ring.records().unwrap(), vec![b"second".to_vec(), b"third".to_vec()]);
+		}
+		{
+			let mut reader = MmapRing::open_read_only(path).unwrap();
+			let mut ring = MmapRing::open(path, 32).unwrap();
+			assert!(reader.push(b"fourth").is_err());
+			ring.push(b"fourth").unwrap();
+			assert_eq!(reader.records().unwrap(), vec![b"third".to_vec(), b"fourth".to_vec()]);
+
+			// simulate torn metadata write; previous metadata will be used
+			let slot = (ring.meta.seq % 2) as usize * META_SLOT_SIZE;
+			unsafe { *ring.ptr.add(slot + 20) ^= 0xff; }
+		}
+		{
+			let ring = MmapRing::open(path, 32).unwrap();
+			assert_eq!(ring.records().unwrap(), vec![b"third".to_vec()]);
+		}
+		remove_file(path).unwrap();
+	}
+}
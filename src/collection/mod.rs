@@ -1,5 +1,79 @@
+#[cfg(feature = "async")]
+pub use async_circular_vec::{AsyncCircularVec, Closed, Pop};
+#[cfg(feature = "std")]
+pub use bit_set::BitSet;
+#[cfg(feature = "std")]
+pub use byte_ring::{ByteRing, ByteRingReader, ByteRingWriter};
+#[cfg(feature = "std")]
+pub use cache::{Cache, Evicted, Policy, ShardedCache};
+pub use circular::Circular;
+pub use circular_array::CircularArray;
+#[cfg(feature = "alloc")]
 pub use circular_vec::CircularVec;
+#[cfg(feature = "std")]
+pub use grid::{Grid, GridView};
+#[cfg(feature = "std")]
+pub use indexed_heap::IndexedHeap;
+#[cfg(feature = "std")]
+pub use interval_map::IntervalMap;
+#[cfg(feature = "std")]
+pub use line_ring::LineRing;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap_ring::MmapRing;
+#[cfg(feature = "std")]
+pub use persistent_vec::PersistentVec;
+#[cfg(feature = "std")]
+pub use rolling_stats::RollingStats;
+#[cfg(feature = "std")]
+pub use rrd::{Archive, Consolidation, Rrd, Series};
+#[cfg(feature = "serde")]
+pub use serde_impl::Truncate;
+#[cfg(feature = "std")]
+pub use slot_map::{SecondaryMap, SlotKey, SlotMap};
+#[cfg(feature = "std")]
+pub use sparse_set::SparseSet;
+#[cfg(feature = "std")]
+pub use time_window::{Clock, SystemClock, TimeWindow};
+#[cfg(feature = "std")]
+pub use timer_wheel::TimerWheel;
 
+#[cfg(feature = "async")]
+mod async_circular_vec;
+#[cfg(feature = "std")]
+mod bit_set;
+#[cfg(feature = "std")]
+mod byte_ring;
+#[cfg(feature = "std")]
+mod cache;
+mod circular;
+mod circular_array;
+#[cfg(feature = "alloc")]
 mod circular_vec;
-mod vec;
-
+#[cfg(feature = "std")]
+mod grid;
+#[cfg(feature = "std")]
+mod indexed_heap;
+#[cfg(feature = "std")]
+mod interval_map;
+#[cfg(feature = "std")]
+mod line_ring;
+#[cfg(all(feature = "mmap", unix))]
+mod mmap_ring;
+#[cfg(feature = "std")]
+mod persistent_vec;
+#[cfg(feature = "std")]
+mod rolling_stats;
+#[cfg(feature = "std")]
+mod rrd;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(feature = "std")]
+mod slot_map;
+#[cfg(feature = "std")]
+mod sparse_set;
+#[cfg(feature = "std")]
+mod time_window;
+#[cfg(feature = "std")]
+mod timer_wheel;
+#[cfg(feature = "alloc")]
+pub mod vec;
@@ -0,0 +1,353 @@
+use std::fmt::{Debug, Formatter};
+use std::ops::{Index, IndexMut};
+use std::sync::Arc;
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+#[derive(Clone)]
+enum Node<T> {
+	Branch(Vec<Arc<Node<T>>>),
+	Leaf(Vec<T>),
+}
+
+impl<T> Node<T> {
+	fn branch(&self) -> &[Arc<Node<T>>] {
+		match self {
+			Node::Branch(it) => it,
+			Node::Leaf(_) => unreachable!("expected branch"),
+		}
+	}
+
+	fn leaf(&self) -> &[T] {
+		match self {
+			Node::Leaf(it) => it,
+			Node::Branch(_) => unreachable!("expected leaf"),
+		}
+	}
+}
+
+impl<T: Clone> Node<T> {
+	fn branch_mut(self: &mut Arc<Self>) -> &mut Vec<Arc<Node<T>>> {
+		match Arc::make_mut(self) {
+			Node::Branch(it) => it,
+			Node::Leaf(_) => unreachable!("expected branch"),
+		}
+	}
+
+	fn leaf_mut(self: &mut Arc<Self>) -> &mut Vec<T> {
+		match Arc::make_mut(self) {
+			Node::Leaf(it) => it,
+			Node::Branch(_) => unreachable!("expected leaf"),
+		}
+	}
+}
+
+/// Persistent vector (32-way trie) with structural sharing
+/// + clone is O(1)
+/// + modifying shared vector copy only O(log n) nodes instead of whole vector
+///
+/// this makes `Arc<PersistentVec<T>>` cheap to modify with `ArcExt::modify`
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::PersistentVec;
+/// let base = (0..1000).collect::<PersistentVec<_>>();
+/// let mut copied = base.clone();
+/// copied.set(500, -1);
+/// copied.push(1000);
+/// assert_eq!((base[500], base.len()), (500, 1000));
+/// assert_eq!((copied[500], copied.len()), (-1, 1001));
+/// ```
+pub struct PersistentVec<T> {
+	root: Arc<Node<T>>,
+	/// last leaf is kept outside of trie to make push O(1)
+	tail: Arc<Node<T>>,
+	len: usize,
+	/// bits to shift at root level
+	shift: usize,
+}
+
+impl<T> Clone for PersistentVec<T> {
+	fn clone(&self) -> Self {
+		Self {
+			root: Arc::clone(&self.root),
+			tail: Arc::clone(&self.tail),
+			len: self.len,
+			shift: self.shift,
+		}
+	}
+}
+
+impl<T> Default for PersistentVec<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> PersistentVec<T> {
+	/// Create new empty vector
+	pub fn new() -> Self {
+		Self {
+			root: Arc::new(Node::Branch(Vec::new())),
+			tail: Arc::new(Node::Leaf(Vec::new())),
+			len: 0,
+			shift: BITS,
+		}
+	}
+
+	/// Get number of elements
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Check if this vector is empty
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// index of first element in tail
+	fn tail_offset(&self) -> usize {
+		if self.len < WIDTH { 0 } else { ((self.len - 1) >> BITS) << BITS }
+	}
+
+	/// get leaf that contains `index`
+	fn leaf_for(&self, index: usize) -> &[T] {
+		if index >= self.tail_offset() {
+			return self.tail.leaf();
+		}
+		let mut node = &self.root;
+		let mut level = self.shift;
+		while level > 0 {
+			node = &node.branch()[(index >> level) & MASK];
+			level -= BITS;
+		}
+		node.leaf()
+	}
+
+	/// Get element at given index
+	pub fn get(&self, index: usize) -> Option<&T> {
+		if index < self.len { Some(&self.leaf_for(index)[index & MASK]) } else { None }
+	}
+
+	/// Get first element
+	pub fn first(&self) -> Option<&T> {
+		self.get(0)
+	}
+
+	/// Get last element
+	pub fn last(&self) -> Option<&T> {
+		self.tail.leaf().last()
+	}
+
+	/// Iterate elements as slices of up to 32 elements
+	pub fn chunks(&self) -> impl Iterator<Item=&[T]> {
+		(0..self.len).step_by(WIDTH).map(|it| self.leaf_for(it))
+	}
+
+	/// Iterate elements
+	pub fn iter(&self) -> impl Iterator<Item=&T> {
+		self.chunks().flatten()
+	}
+
+	/// Check if both vectors share same nodes (both are clone of same vector without modification)
+	pub fn ptr_eq(&self, other: &Self) -> bool {
+		Arc::ptr_eq(&self.root, &other.root) && Arc::ptr_eq(&self.tail, &other.tail) && self.len == other.len
+	}
+}
+
+impl<T: Clone> PersistentVec<T> {
+	/// Get mutable element at given index; shared nodes on its path will be copied
+	pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+		if index >= self.len {
+			return None;
+		}
+		if index >= self.tail_offset() {
+			return Some(&mut self.tail.leaf_mut()[index & MASK]);
+		}
+		let mut node = &mut self.root;
+		let mut level = self.shift;
+		while level > 0 {
+			node = &mut node.branch_mut()[(index >> level) & MASK];
+			level -= BITS;
+		}
+		Some(&mut node.leaf_mut()[index & MASK])
+	}
+
+	/// Replace element at given index and return old element
+	/// # Panics
+	/// if `index` is out of bounds
+	pub fn set(&mut self, index: usize, value: T) -> T {
+		let len = self.len;
+		let slot = self.get_mut(index).unwrap_or_else(|| panic!("index {index} out of bounds (len {len})"));
+		std::mem::replace(slot, value)
+	}
+
+	/// Append element to the end of this vector
+	pub fn push(&mut self, value: T) {
+		if self.tail.leaf().len() == WIDTH {
+			let leaf = std::mem::replace(&mut self.tail, Arc::new(Node::Leaf(Vec::with_capacity(WIDTH))));
+			if (self.len >> BITS) > (1 << self.shift) {
+				// root is full; add new level
+				let path = new_path(self.shift, leaf);
+				let root = std::mem::replace(&mut self.root, Arc::new(Node::Branch(Vec::new())));
+				self.root = Arc::new(Node::Branch(vec![root, path]));
+				self.shift += BITS;
+			} else {
+				push_leaf(self.len, self.shift, &mut self.root, leaf);
+			}
+		}
+		self.tail.leaf_mut().push(value);
+		self.len += 1;
+	}
+
+	/// Remove last element
+	pub fn pop(&mut self) -> Option<T> {
+		if self.len == 0 {
+			return None;
+		}
+		let value = self.tail.leaf_mut().pop();
+		self.len -= 1;
+		if self.len > 0 && self.tail.leaf().is_empty() {
+			self.tail = pop_leaf(self.shift, &mut self.root);
+			while self.shift > BITS && self.root.branch().len() == 1 {
+				self.root = Arc::clone(&self.root.branch()[0]);
+				self.shift -= BITS;
+			}
+		}
+		value
+	}
+
+	/// Remove all elements
+	pub fn clear(&mut self) {
+		*self = Self::new();
+	}
+}
+
+/// wrap `node` with branches until it reach `level`
+fn new_path<T>(level: usize, node: Arc<Node<T>>) -> Arc<Node<T>> {
+	if level == 0 { node } else { Arc::new(Node::Branch(vec![new_path(level - BITS, node)])) }
+}
+
+/// insert full leaf as rightmost leaf of trie; `len` is number of elements before insertion
+fn push_leaf<T: Clone>(len: usize, level: usize, node: &mut Arc<Node<T>>, leaf: Arc<Node<T>>) {
+	let children = node.branch_mut();
+	let idx = ((len - 1) >> level) & MASK;
+	if level == BITS {
+		children.push(leaf);
+	} else if idx < children.len() {
+		push_leaf(len, level - BITS, &mut children[idx], leaf);
+	} else {
+		children.push(new_path(level - BITS, leaf));
+	}
+}
+
+/// remove rightmost leaf of trie
+fn pop_leaf<T: Clone>(level: usize, node: &mut Arc<Node<T>>) -> Arc<Node<T>> {
+	let children = node.branch_mut();
+	if level == BITS {
+		return children.pop().expect("trie is empty");
+	}
+	let last = children.last_mut().expect("trie is empty");
+	let leaf = pop_leaf(level - BITS, last);
+	if last.branch().is_empty() {
+		children.pop();
+	}
+	leaf
+}
+
+impl<T> Index<usize> for PersistentVec<T> {
+	type Output = T;
+
+	fn index(&self, index: usize) -> &Self::Output {
+		self.get(index).unwrap_or_else(|| panic!("index {index} out of bounds (len {})", self.len))
+	}
+}
+
+impl<T: Clone> IndexMut<usize> for PersistentVec<T> {
+	fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+		let len = self.len;
+		self.get_mut(index).unwrap_or_else(|| panic!("index {index} out of bounds (len {len})"))
+	}
+}
+
+impl<T: Debug> Debug for PersistentVec<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_list().entries(self.iter()).finish()
+	}
+}
+
+impl<T: PartialEq> PartialEq for PersistentVec<T> {
+	fn eq(&self, other: &Self) -> bool {
+		self.len == other.len && self.iter().eq(other.iter())
+	}
+}
+
+impl<T: Eq> Eq for PersistentVec<T> {}
+
+impl<T: Clone> Extend<T> for PersistentVec<T> {
+	fn extend<I: IntoIterator<Item=T>>(&mut self, iter: I) {
+		for it in iter { self.push(it); }
+	}
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVec<T> {
+	fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
+		let mut vec = Self::new();
+		vec.extend(iter);
+		vec
+	}
+}
+
+impl<T: Clone> From<Vec<T>> for PersistentVec<T> {
+	fn from(value: Vec<T>) -> Self {
+		value.into_iter().collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::PersistentVec;
+
+	#[test]
+	fn test_persistent_vec() {
+		let mut vec = PersistentVec::new();
+		let mut expected = Vec::new();
+		// enough to create 3 levels
+		for i in 0..40000 {
+			vec.push(i);
+			expected.push(i);
+		}
+		let snapshot = vec.clone();
+		assert!(snapshot.ptr_eq(&vec));
+		for i in (0..40000).step_by(7) {
+			vec[i] *= 2;
+			expected[i] *= 2;
+		}
+		assert!(vec.iter().eq(expected.iter()));
+		assert!(snapshot.iter().copied().eq(0..40000));
+		for _ in 0..39000 {
+			assert_eq!(vec.pop(), expected.pop());
+		}
+		assert!(vec.iter().eq(expected.iter()));
+		assert_eq!(vec.last(), expected.last());
+		assert_eq!(snapshot.len(), 40000);
+		while vec.pop().is_some() {}
+		assert!(vec.is_empty() && vec.get(0).is_none());
+		vec.push(1);
+		assert_eq!(vec, PersistentVec::from(vec![1]));
+	}
+
+	#[cfg(feature = "mutation")]
+	#[test]
+	fn test_persistent_vec_arc_ext() {
+		use std::sync::Arc;
+		use crate::ext::ArcExt;
+
+		let mut shared = Arc::new((0..10000).collect::<PersistentVec<_>>());
+		let reader = Arc::clone(&shared);
+		let old = shared.modify(|it| { it.set(42, 0); });
+		assert!(Arc::ptr_eq(&old, &reader));
+		assert_eq!((shared[42], reader[42]), (0, 42));
+	}
+}
@@ -0,0 +1,303 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::collections::btree_map::Entry;
+
+use crate::collection::CircularVec;
+
+/// relative accuracy of [RollingStats::percentile]
+const SKETCH_ACCURACY: f64 = 0.01;
+
+/// Log-bucketed histogram that give approximate quantile with relative error  
+/// insert and remove are O(log buckets)
+struct Sketch {
+	gamma: f64,
+	gamma_ln: f64,
+	negative: BTreeMap<i32, usize>,
+	zero: usize,
+	positive: BTreeMap<i32, usize>,
+}
+
+impl Sketch {
+	fn new() -> Self {
+		let gamma = (1.0 + SKETCH_ACCURACY) / (1.0 - SKETCH_ACCURACY);
+		Self {
+			gamma,
+			gamma_ln: gamma.ln(),
+			negative: BTreeMap::new(),
+			zero: 0,
+			positive: BTreeMap::new(),
+		}
+	}
+
+	#[inline]
+	fn index(&self, value: f64) -> i32 {
+		(value.abs().ln() / self.gamma_ln).ceil() as i32
+	}
+
+	#[inline]
+	fn value(&self, index: i32) -> f64 {
+		2.0 * self.gamma.powi(index) / (self.gamma + 1.0)
+	}
+
+	fn map(&mut self, value: f64) -> &mut BTreeMap<i32, usize> {
+		if value > 0.0 { &mut self.positive } else { &mut self.negative }
+	}
+
+	fn insert(&mut self, value: f64) {
+		if value.is_nan() { return; }
+		if value == 0.0 {
+			self.zero += 1;
+			return;
+		}
+		let index = self.index(value);
+		*self.map(value).entry(index).or_insert(0) += 1;
+	}
+
+	fn remove(&mut self, value: f64) {
+		if value.is_nan() { return; }
+		if value == 0.0 {
+			self.zero -= 1;
+			return;
+		}
+		let index = self.index(value);
+		if let Entry::Occupied(mut count) = self.map(value).entry(index) {
+			*count.get_mut() -= 1;
+			if *count.get() == 0 { count.remove(); }
+		}
+	}
+
+	/// `rank` is zero-based position in sorted order
+	fn rank(&self, rank: usize) -> Option<f64> {
+		let mut seen = 0;
+		for (index, count) in self.negative.iter().rev() {
+			seen += count;
+			if rank < seen { return Some(-self.value(*index)); }
+		}
+		seen += self.zero;
+		if rank < seen { return Some(0.0); }
+		for (index, count) in self.positive.iter() {
+			seen += count;
+			if rank < seen { return Some(self.value(*index)); }
+		}
+		None
+	}
+}
+
+/// [CircularVec] that maintain rolling statistics of its content  
+/// statistics are updated on push and evict instead of recomputed on each query  
+/// ### Usage
+/// + load average or latency of latest N samples
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::RollingStats;
+/// let mut stats = RollingStats::new(3);
+/// for x in [4.0, 1.0, 2.0, 3.0] { stats.push(x); }
+/// // 4.0 was evicted
+/// assert_eq!(stats.sum(), 6.0);
+/// assert_eq!(stats.mean(), Some(2.0));
+/// assert_eq!(stats.min(), Some(1.0));
+/// assert_eq!(stats.max(), Some(3.0));
+/// ```
+pub struct RollingStats<T> {
+	vec: CircularVec<T>,
+	/// sequence number of next pushed element
+	seq: u64,
+	sum: f64,
+	mean: f64,
+	/// sum of squares of differences from the mean (Welford)
+	m2: f64,
+	/// monotonic increasing deque of (seq, value)
+	min: VecDeque<(u64, T)>,
+	/// monotonic decreasing deque of (seq, value)
+	max: VecDeque<(u64, T)>,
+	alpha: f64,
+	ewma: Option<f64>,
+	sketch: Sketch,
+}
+
+impl<T: Copy + Into<f64>> RollingStats<T> {
+	/// Create new rolling statistics with given window size  
+	/// EWMA smoothing factor default to `2 / (size + 1)`
+	pub fn new(size: usize) -> Self {
+		Self {
+			vec: CircularVec::new(size),
+			seq: 0,
+			sum: 0.0,
+			mean: 0.0,
+			m2: 0.0,
+			min: VecDeque::new(),
+			max: VecDeque::new(),
+			alpha: 2.0 / (size as f64 + 1.0),
+			ewma: None,
+			sketch: Sketch::new(),
+		}
+	}
+
+	/// Set EWMA smoothing factor; must be in range `(0, 1]`
+	#[must_use]
+	pub fn with_alpha(mut self, alpha: f64) -> Self {
+		assert!(alpha > 0.0 && alpha <= 1.0);
+		self.alpha = alpha;
+		self
+	}
+
+	/// Append value to ends of window; if window is full it will return oldest element
+	pub fn push(&mut self, item: T) -> Option<T> {
+		let oldest = self.seq - self.vec.len() as u64;
+		let evicted = self.vec.push(item);
+		if let Some(old) = evicted {
+			// new element is already in vec
+			self.remove(oldest, old, self.vec.len() - 1);
+		}
+
+		let seq = self.seq;
+		self.seq += 1;
+		let value = item.into();
+		self.sum += value;
+		let n = self.vec.len() as f64;
+		let delta = value - self.mean;
+		self.mean += delta / n;
+		self.m2 += delta * (value - self.mean);
+
+		while matches!(self.min.back(), Some((_, it)) if (*it).into() >= value) { self.min.pop_back(); }
+		self.min.push_back((seq, item));
+		while matches!(self.max.back(), Some((_, it)) if (*it).into() <= value) { self.max.pop_back(); }
+		self.max.push_back((seq, item));
+
+		self.ewma = Some(match self.ewma {
+			Some(ewma) => ewma + self.alpha * (value - ewma),
+			None => value,
+		});
+		self.sketch.insert(value);
+		evicted
+	}
+
+	/// Try to remove oldest element from window
+	pub fn pop(&mut self) -> Option<T> {
+		let oldest = self.seq - self.vec.len() as u64;
+		let item = self.vec.pop()?;
+		self.remove(oldest, item, self.vec.len());
+		Some(item)
+	}
+
+	/// update statistics after element was removed from vec; `n` is number of remaining elements
+	fn remove(&mut self, seq: u64, item: T, n: usize) {
+		let value = item.into();
+		self.sum -= value;
+		if n == 0 {
+			self.sum = 0.0;
+			self.mean = 0.0;
+			self.m2 = 0.0;
+		} else {
+			let delta = value - self.mean;
+			self.mean -= delta / n as f64;
+			self.m2 = (self.m2 - delta * (value - self.mean)).max(0.0);
+		}
+		if matches!(self.min.front(), Some((it, _)) if *it == seq) { self.min.pop_front(); }
+		if matches!(self.max.front(), Some((it, _)) if *it == seq) { self.max.pop_front(); }
+		self.sketch.remove(value);
+	}
+
+	/// Get length of this window
+	pub fn len(&self) -> usize {
+		self.vec.len()
+	}
+
+	/// Check if this window is empty
+	pub fn is_empty(&self) -> bool {
+		self.vec.is_empty()
+	}
+
+	/// Get underlying circular vec
+	pub fn as_circular_vec(&self) -> &CircularVec<T> {
+		&self.vec
+	}
+
+	/// Sum of elements in window
+	pub fn sum(&self) -> f64 {
+		self.sum
+	}
+
+	/// Arithmetic mean of elements in window
+	pub fn mean(&self) -> Option<f64> {
+		if self.is_empty() { None } else { Some(self.mean) }
+	}
+
+	/// Population variance of elements in window
+	pub fn variance(&self) -> Option<f64> {
+		if self.is_empty() { None } else { Some(self.m2 / self.len() as f64) }
+	}
+
+	/// Population standard deviation of elements in window
+	pub fn stddev(&self) -> Option<f64> {
+		self.variance().map(f64::sqrt)
+	}
+
+	/// Smallest element in window
+	pub fn min(&self) -> Option<T> {
+		self.min.front().map(|(_, it)| *it)
+	}
+
+	/// Largest element in window
+	pub fn max(&self) -> Option<T> {
+		self.max.front().map(|(_, it)| *it)
+	}
+
+	/// Exponentially weighted moving average of every pushed element  
+	/// unlike other statistics this value is not affected by eviction
+	pub fn ewma(&self) -> Option<f64> {
+		self.ewma
+	}
+
+	/// Approximate percentile of elements in window (within 1% relative error)  
+	/// `p` must be in range `[0, 100]`
+	pub fn percentile(&self, p: f64) -> Option<f64> {
+		assert!((0.0..=100.0).contains(&p));
+		let len = self.len();
+		if len == 0 { return None; }
+		let rank = (p / 100.0 * (len - 1) as f64).round() as usize;
+		self.sketch.rank(rank)
+	}
+
+	/// Approximate median
+	pub fn p50(&self) -> Option<f64> {
+		self.percentile(50.0)
+	}
+
+	/// Approximate 95th percentile
+	pub fn p95(&self) -> Option<f64> {
+		self.percentile(95.0)
+	}
+
+	/// Approximate 99th percentile
+	pub fn p99(&self) -> Option<f64> {
+		self.percentile(99.0)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::RollingStats;
+
+	#[test]
+	fn test_rolling_stats() {
+		let mut stats = RollingStats::<i32>::new(100).with_alpha(0.5);
+		for i in -50..=200 { stats.push(i); }
+		// window contains 101..=200
+		assert_eq!(stats.len(), 100);
+		assert_eq!(stats.sum(), (101..=200).sum::<i32>() as f64);
+		assert!((stats.mean().unwrap() - 150.5).abs() < 1e-9);
+		assert!((stats.variance().unwrap() - 833.25).abs() < 1e-6);
+		assert_eq!(stats.min(), Some(101));
+		assert_eq!(stats.max(), Some(200));
+		assert!((stats.ewma().unwrap() - 199.0).abs() < 1e-6);
+		let p50 = stats.p50().unwrap();
+		assert!((p50 - 150.0).abs() / 150.0 <= 0.02, "{p50}");
+		let p99 = stats.p99().unwrap();
+		assert!((p99 - 199.0).abs() / 199.0 <= 0.02, "{p99}");
+
+		while stats.pop().is_some() {}
+		assert_eq!(stats.mean(), None);
+		assert_eq!(stats.min(), None);
+		assert_eq!(stats.p50(), None);
+	}
+}
@@ -0,0 +1,412 @@
+use std::io;
+use std::io::{ErrorKind, Read, Write};
+
+use crate::collection::CircularVec;
+
+static RRD_HEADER: &[u8] = b"RRD1";
+
+/// Function used to merge multiple values into single row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Consolidation {
+	/// Arithmetic mean of values
+	Average = 0,
+	/// Smallest value
+	Min = 1,
+	/// Largest value
+	Max = 2,
+	/// Latest value
+	Last = 3,
+}
+
+impl Consolidation {
+	fn from_u8(value: u8) -> Option<Self> {
+		match value {
+			0 => Some(Self::Average),
+			1 => Some(Self::Min),
+			2 => Some(Self::Max),
+			3 => Some(Self::Last),
+			_ => None,
+		}
+	}
+}
+
+/// Definition of single archive in [Rrd]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Archive {
+	/// Duration of single row in seconds
+	pub step: u64,
+	/// Number of rows to keep
+	pub rows: usize,
+	/// How values inside single row are merged
+	pub consolidation: Consolidation,
+}
+
+impl Archive {
+	/// Create new archive definition
+	pub fn new(step: u64, rows: usize, consolidation: Consolidation) -> Self {
+		Self { step, rows, consolidation }
+	}
+}
+
+/// Values of row that isn't completed yet
+#[derive(Debug, Clone, Copy)]
+struct Pending {
+	sum: f64,
+	count: u32,
+	min: f64,
+	max: f64,
+	last: f64,
+}
+
+impl Pending {
+	const EMPTY: Pending = Pending {
+		sum: 0.0,
+		count: 0,
+		min: f64::INFINITY,
+		max: f64::NEG_INFINITY,
+		last: f64::NAN,
+	};
+
+	fn add(&mut self, value: f64) {
+		// unknown value
+		if value.is_nan() { return; }
+		self.sum += value;
+		self.count += 1;
+		self.min = self.min.min(value);
+		self.max = self.max.max(value);
+		self.last = value;
+	}
+
+	fn value(&self, consolidation: Consolidation) -> f64 {
+		if self.count == 0 { return f64::NAN; }
+		match consolidation {
+			Consolidation::Average => self.sum / self.count as f64,
+			Consolidation::Min => self.min,
+			Consolidation::Max => self.max,
+			Consolidation::Last => self.last,
+		}
+	}
+}
+
+struct ArchiveState {
+	archive: Archive,
+	/// completed rows; newest row belong to `slot - 1`
+	rows: CircularVec<f64>,
+	/// slot (timestamp / step) of pending row
+	slot: Option<u64>,
+	pending: Pending,
+}
+
+impl ArchiveState {
+	fn new(archive: Archive) -> Self {
+		Self {
+			archive,
+			rows: CircularVec::new(archive.rows),
+			slot: None,
+			pending: Pending::EMPTY,
+		}
+	}
+
+	/// same as [ArchiveState::new] but rows are allocated as they are pushed;
+	/// used when number of rows come from untrusted data
+	fn lazy(archive: Archive) -> Self {
+		Self {
+			archive,
+			rows: CircularVec::new(1),
+			slot: None,
+			pending: Pending::EMPTY,
+		}
+	}
+
+	/// push completed row; grow rows up to `archive.rows` when they are full
+	fn push_row(&mut self, value: f64) {
+		let capacity = self.rows.capacity();
+		if self.rows.is_full() && capacity < self.archive.rows {
+			let mut rows = CircularVec::new(capacity.saturating_mul(2).min(self.archive.rows));
+			for row in self.rows.take() { rows.push(row); }
+			self.rows = rows;
+		}
+		self.rows.push(value);
+	}
+
+	/// Add value to this archive; return completed row (timestamp, value) if slot was changed
+	fn feed(&mut self, timestamp: u64, value: f64) -> Option<(u64, f64)> {
+		let step = self.archive.step;
+		let slot = timestamp / step;
+		let mut completed = None;
+		match self.slot {
+			Some(current) if slot < current => { return None; }
+			Some(current) if slot > current => {
+				let row = self.pending.value(self.archive.consolidation);
+				self.push_row(row);
+				completed = Some((current * step, row));
+				// fill missing rows with unknown value
+				let missing = (slot - current - 1).min(self.archive.rows as u64);
+				for _ in 0..missing { self.push_row(f64::NAN); }
+				self.pending = Pending::EMPTY;
+			}
+			_ => {}
+		}
+		self.slot = Some(slot);
+		self.pending.add(value);
+		completed
+	}
+
+	/// Timestamp of oldest completed row
+	fn oldest(&self) -> Option<u64> {
+		let slot = self.slot?;
+		if self.rows.is_empty() { return None; }
+		Some((slot - self.rows.len() as u64) * self.archive.step)
+	}
+}
+
+/// Values returned from [Rrd::fetch]
+#[derive(Debug, Clone, PartialEq)]
+pub struct Series {
+	/// Timestamp of first value
+	pub start: u64,
+	/// Duration between values in seconds
+	pub step: u64,
+	/// Consolidated values; unknown value is NaN
+	pub values: Vec<f64>,
+}
+
+/// Round-robin time series store that keep multiple archives with different resolution  
+/// each coarser archive is fed by completed rows of previous (finer) archive  
+/// timestamp is number of seconds (eg. unix time)
+/// ### Usage
+/// + keep 1s resolution for 15 minutes, 1 minute for a day and 1 hour for a year
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::{Archive, Consolidation, Rrd};
+/// let mut rrd = Rrd::new(&[
+///     Archive::new(1, 900, Consolidation::Average),
+///     Archive::new(60, 1440, Consolidation::Average),
+/// ]);
+/// for ts in 0..120 { rrd.update(ts, ts as f64); }
+/// let series = rrd.fetch(100, 110).unwrap();
+/// assert_eq!(series.step, 1);
+/// assert_eq!(series.values.len(), 11);
+/// ```
+pub struct Rrd {
+	archives: Vec<ArchiveState>,
+}
+
+impl Rrd {
+	/// Create new rrd with given archives; archives must be ordered from finest resolution  
+	/// and step of each archive must be multiple of previous step
+	pub fn new(archives: &[Archive]) -> Self {
+		if let Err(err) = Self::check(archives) {
+			panic!("{}", err);
+		}
+		Self {
+			archives: archives.iter().copied().map(ArchiveState::new).collect(),
+		}
+	}
+
+	/// check archive definitions that are accepted by [Rrd::new]
+	fn check(archives: &[Archive]) -> Result<(), &'static str> {
+		if archives.is_empty() {
+			return Err("rrd need at least one archive");
+		}
+		for (i, archive) in archives.iter().enumerate() {
+			if archive.step == 0 || archive.rows == 0 {
+				return Err("step and rows of archive must not be 0");
+			}
+			if i > 0 {
+				let prev = archives[i - 1].step;
+				if archive.step <= prev || archive.step % prev != 0 {
+					return Err("step of archive must be multiple of previous step");
+				}
+			}
+		}
+		Ok(())
+	}
+
+	/// Get archive definitions
+	pub fn archives(&self) -> impl Iterator<Item=&Archive> {
+		self.archives.iter().map(|it| &it.archive)
+	}
+
+	/// Add new value at given timestamp  
+	/// return false if timestamp is older than latest row of finest archive
+	pub fn update(&mut self, timestamp: u64, value: f64) -> bool {
+		let finest = &self.archives[0];
+		if matches!(finest.slot, Some(slot) if timestamp / finest.archive.step < slot) {
+			return false;
+		}
+		let mut next = Some((timestamp, value));
+		for archive in self.archives.iter_mut() {
+			match next {
+				Some((timestamp, value)) => { next = archive.feed(timestamp, value); }
+				None => { break; }
+			}
+		}
+		true
+	}
+
+	/// Get completed rows between `start` and `end` (inclusive)  
+	/// finest archive that still contains `start` will be used,  
+	/// otherwise archive with longest history will be used
+	pub fn fetch(&self, start: u64, end: u64) -> Option<Series> {
+		let available = || self.archives.iter().filter_map(|it| Some((it, it.oldest()?)));
+		let (archive, oldest) = available()
+			.find(|(_, oldest)| *oldest <= start)
+			.or_else(|| available().min_by_key(|(_, oldest)| *oldest))?;
+		let step = archive.archive.step;
+		let mut series = Series { start: 0, step, values: Vec::new() };
+		for (i, value) in archive.rows.iter().enumerate() {
+			let ts = oldest + i as u64 * step;
+			if ts < start { continue; }
+			if ts > end { break; }
+			if series.values.is_empty() { series.start = ts; }
+			series.values.push(*value);
+		}
+		if series.values.is_empty() { None } else { Some(series) }
+	}
+
+	/// Read rrd from reader that was written by [Rrd::write]  
+	/// archives are validated like [Rrd::new]; return [ErrorKind::InvalidData] if data is invalid  
+	/// rows are allocated as they are read, so declared number of rows doesn't allocate memory up front
+	pub fn read<R: Read>(r: &mut R) -> io::Result<Self> {
+		let mut header = [0u8; 4];
+		r.read_exact(&mut header)?;
+		if header != RRD_HEADER {
+			return Err(ErrorKind::InvalidData.into());
+		}
+		let count = read_u32(r)?;
+		let mut archives = Vec::new();
+		let mut definitions = Vec::new();
+		for _ in 0..count {
+			let step = read_u64(r)?;
+			let rows = read_u32(r)? as usize;
+			let mut cf = [0u8];
+			r.read_exact(&mut cf)?;
+			let consolidation = Consolidation::from_u8(cf[0]).ok_or(ErrorKind::InvalidData)?;
+			definitions.push(Archive::new(step, rows, consolidation));
+			Self::check(&definitions).map_err(|it| io::Error::new(ErrorKind::InvalidData, it))?;
+			let mut state = ArchiveState::lazy(Archive::new(step, rows, consolidation));
+			state.slot = match read_u64(r)? {
+				u64::MAX => None,
+				slot => Some(slot),
+			};
+			state.pending = Pending {
+				sum: read_f64(r)?,
+				count: read_u32(r)?,
+				min: read_f64(r)?,
+				max: read_f64(r)?,
+				last: read_f64(r)?,
+			};
+			let len = read_u32(r)? as usize;
+			if len > rows || (len > 0 && state.slot.is_none_or(|it| it < len as u64)) {
+				return Err(ErrorKind::InvalidData.into());
+			}
+			for _ in 0..len { state.push_row(read_f64(r)?); }
+			archives.push(state);
+		}
+		Self::check(&definitions).map_err(|it| io::Error::new(ErrorKind::InvalidData, it))?;
+		Ok(Self { archives })
+	}
+
+	/// Write rrd in compact binary format
+	pub fn write<W: Write>(&self, w: &mut W) -> io::Result<()> {
+		w.write_all(RRD_HEADER)?;
+		w.write_all(&(self.archives.len() as u32).to_le_bytes())?;
+		for state in &self.archives {
+			let Archive { step, rows, consolidation } = state.archive;
+			w.write_all(&step.to_le_bytes())?;
+			w.write_all(&(rows as u32).to_le_bytes())?;
+			w.write_all(&[consolidation as u8])?;
+			w.write_all(&state.slot.unwrap_or(u64::MAX).to_le_bytes())?;
+			let Pending { sum, count, min, max, last } = state.pending;
+			w.write_all(&sum.to_le_bytes())?;
+			w.write_all(&count.to_le_bytes())?;
+			w.write_all(&min.to_le_bytes())?;
+			w.write_all(&max.to_le_bytes())?;
+			w.write_all(&last.to_le_bytes())?;
+			w.write_all(&(state.rows.len() as u32).to_le_bytes())?;
+			for value in state.rows.iter() {
+				w.write_all(&value.to_le_bytes())?;
+			}
+		}
+		Ok(())
+	}
+}
+
+macro_rules! read_le {
+    ($name:ident, $var:ident, $bytes:literal) => {
+		fn $name<R: Read>(r: &mut R) -> io::Result<$var> {
+			let mut buf = [0; $bytes];
+			r.read_exact(&mut buf)?;
+			Ok($var::from_le_bytes(buf))
+		}
+    };
+}
+
+read_le! {read_u32, u32, 4}
+read_le! {read_u64, u64, 8}
+read_le! {read_f64, f64, 8}
+
+#[cfg(test)]
+mod test {
+	use std::io::ErrorKind;
+
+	use crate::collection::{Archive, Consolidation, Rrd};
+
+	#[test]
+	fn test_rrd() {
+		let mut rrd = Rrd::new(&[
+			Archive::new(1, 10, Consolidation::Average),
+			Archive::new(5, 10, Consolidation::Max),
+		]);
+		for ts in 0..30 { rrd.update(ts, ts as f64); }
+		// gap; 30..=34 are unknown
+		rrd.update(35, 35.0);
+		rrd.update(36, 36.0);
+		assert!(!rrd.update(20, 20.0));
+
+		// only finest archive contains recent rows
+		let series = rrd.fetch(33, 40).unwrap();
+		assert_eq!(series.step, 1);
+		assert_eq!(series.start, 33);
+		assert!(series.values[..2].iter().all(|it| it.is_nan()));
+		assert_eq!(series.values[2], 35.0);
+
+		// older rows are in coarser archive
+		let series = rrd.fetch(0, 20).unwrap();
+		assert_eq!(series.step, 5);
+		assert_eq!(series.start, 0);
+		assert_eq!(series.values, vec![4.0, 9.0, 14.0, 19.0, 24.0]);
+
+		let mut buf = Vec::new();
+		rrd.write(&mut buf).unwrap();
+		let mut loaded = Rrd::read(&mut buf.as_slice()).unwrap();
+		assert_eq!(loaded.fetch(0, 20), rrd.fetch(0, 20));
+		loaded.update(40, 40.0);
+		rrd.update(40, 40.0);
+		assert_eq!(loaded.fetch(30, 40).map(|it| it.values.len()), rrd.fetch(30, 40).map(|it| it.values.len()));
+		assert!(Rrd::read(&mut &buf[..buf.len() - 1]).is_err());
+		// second archive start after header (8 bytes) and first archive (61 bytes + 8 bytes per row)
+		let len = u32::from_le_bytes(buf[8 + 57..8 + 61].try_into().unwrap()) as usize;
+		let offset = 8 + 61 + 8 * len;
+		// step that isn't larger than previous step
+		buf[offset..offset + 8].copy_from_slice(&1u64.to_le_bytes());
+		assert_eq!(Rrd::read(&mut buf.as_slice()).err().map(|it| it.kind()), Some(ErrorKind::InvalidData));
+
+		// truncated file that declare huge archive fail without allocating it
+		let mut buf = b"RRD1".to_vec();
+		buf.extend(1u32.to_le_bytes());
+		buf.extend(1u64.to_le_bytes());
+		buf.extend(u32::MAX.to_le_bytes());
+		buf.push(Consolidation::Last as u8);
+		// slot
+		buf.extend((1u64 << 40).to_le_bytes());
+		assert_eq!(Rrd::read(&mut buf.as_slice()).err().map(|it| it.kind()), Some(ErrorKind::UnexpectedEof));
+		// pending row, then huge number of stored rows but only one row
+		buf.extend([0; 36]);
+		buf.extend(u32::MAX.to_le_bytes());
+		buf.extend(1f64.to_le_bytes());
+		assert_eq!(Rrd::read(&mut buf.as_slice()).err().map(|it| it.kind()), Some(ErrorKind::UnexpectedEof));
+	}
+}
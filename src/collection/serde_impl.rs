@@ -0,0 +1,313 @@
+use std::hash::Hash;
+use std::ops::Range;
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::de::Error;
+use serde::ser::SerializeStruct;
+
+use crate::collection::{BitSet, ByteRing, CircularArray, CircularVec, Grid, IndexedHeap, IntervalMap, PersistentVec};
+
+// Not serializable by design:
+// + `LineRing`, `RollingStats`: hold configuration and state derived from discarded data (partial line, EWMA);
+//   serialize lines or values and push them into new instance instead
+// + `SlotMap`: keys embed slot versions, so keys of restored map would not be valid for values they referred to
+// + `SparseSet`: memory is proportional to the largest id, so untrusted input could force huge allocation;
+//   serialize `as_slice()` and collect validated ids instead
+// + `Cache`: holds clock, weigher closure and eviction state; serialize entries and insert them into new cache
+
+/// Deserialize wrapper that discard oldest elements instead of rejecting data longer than capacity  
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::{CircularVec, Truncate};
+/// let json = r#"{"capacity":2,"items":[1,2,3]}"#;
+/// assert!(serde_json::from_str::<CircularVec<i32>>(json).is_err());
+/// let Truncate(vec) = serde_json::from_str::<Truncate<CircularVec<i32>>>(json).unwrap();
+/// assert_eq!(vec.iter().collect::<Vec<_>>(), vec![&2, &3]);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Truncate<C>(pub C);
+
+/// Serialized form of every circular collection; items are ordered from oldest
+#[derive(Deserialize)]
+struct Repr<T> {
+	capacity: usize,
+	items: Vec<T>,
+}
+
+impl<T> Repr<T> {
+	/// check length of items against capacity
+	fn items<E: Error>(mut self, truncate: bool) -> Result<Vec<T>, E> {
+		let len = self.items.len();
+		if len > self.capacity {
+			if !truncate {
+				return Err(E::custom(format_args!("{len} items exceed capacity {}", self.capacity)));
+			}
+			self.items.drain(..len - self.capacity);
+		}
+		Ok(self.items)
+	}
+}
+
+/// Collection that can be created from [Repr]
+trait FromRepr<T>: Sized {
+	fn from_repr<E: Error>(repr: Repr<T>, truncate: bool) -> Result<Self, E>;
+}
+
+/// serialize `capacity` and `items` fields
+fn serialize_repr<S, F, I>(serializer: S, capacity: usize, items: F) -> Result<S::Ok, S::Error>
+	where S: Serializer,
+	      F: Fn() -> I,
+	      I: Iterator,
+	      I::Item: Serialize {
+	struct Items<F>(F);
+
+	impl<F, I> Serialize for Items<F> where F: Fn() -> I, I: Iterator, I::Item: Serialize {
+		fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+			serializer.collect_seq((self.0)())
+		}
+	}
+
+	let mut state = serializer.serialize_struct("Circular", 2)?;
+	state.serialize_field("capacity", &capacity)?;
+	state.serialize_field("items", &Items(items))?;
+	state.end()
+}
+
+impl<T: Serialize> Serialize for CircularVec<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serialize_repr(serializer, self.capacity(), || self.iter())
+	}
+}
+
+impl<T> FromRepr<T> for CircularVec<T> {
+	fn from_repr<E: Error>(repr: Repr<T>, truncate: bool) -> Result<Self, E> {
+		// capacity isn't trusted; memory is allocated by number of items
+		let mut vec = CircularVec::lazy(repr.capacity);
+		for item in repr.items(truncate)? { vec.push(item); }
+		Ok(vec)
+	}
+}
+
+impl<T: Serialize, const N: usize> Serialize for CircularArray<T, N> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serialize_repr(serializer, N, || self.iter())
+	}
+}
+
+impl<T, const N: usize> FromRepr<T> for CircularArray<T, N> {
+	fn from_repr<E: Error>(repr: Repr<T>, truncate: bool) -> Result<Self, E> {
+		if repr.capacity != N {
+			return Err(E::custom(format_args!("expected capacity {N}, found {}", repr.capacity)));
+		}
+		let mut arr = CircularArray::new();
+		for item in repr.items(truncate)? { arr.push(item); }
+		Ok(arr)
+	}
+}
+
+impl Serialize for ByteRing {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let (first, second) = self.as_slices();
+		serialize_repr(serializer, self.capacity(), || first.iter().chain(second))
+	}
+}
+
+impl FromRepr<u8> for ByteRing {
+	fn from_repr<E: Error>(repr: Repr<u8>, truncate: bool) -> Result<Self, E> {
+		let mut ring = ByteRing::lazy(repr.capacity);
+		ring.write_overwrite(&repr.items(truncate)?);
+		Ok(ring)
+	}
+}
+
+macro_rules! impl_deserialize {
+    ($ty:ty, $item:ty $(, $generic:tt)*) => {
+		impl<'de, $($generic,)*> Deserialize<'de> for $ty where $item: Deserialize<'de> {
+			fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				<$ty>::from_repr(Repr::deserialize(deserializer)?, false)
+			}
+		}
+
+		impl<'de, $($generic,)*> Deserialize<'de> for Truncate<$ty> where $item: Deserialize<'de> {
+			fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+				<$ty>::from_repr(Repr::deserialize(deserializer)?, true).map(Truncate)
+			}
+		}
+    };
+}
+
+impl_deserialize!(CircularVec<T>, T, T);
+impl_deserialize!(ByteRing, u8);
+
+impl<'de, T, const N: usize> Deserialize<'de> for CircularArray<T, N> where T: Deserialize<'de> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Self::from_repr(Repr::deserialize(deserializer)?, false)
+	}
+}
+
+impl<'de, T, const N: usize> Deserialize<'de> for Truncate<CircularArray<T, N>> where T: Deserialize<'de> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		CircularArray::from_repr(Repr::deserialize(deserializer)?, true).map(Truncate)
+	}
+}
+
+/// Serialized form of [Grid]; items are row-major
+#[derive(Deserialize)]
+#[serde(rename = "Grid")]
+struct GridRepr<T> {
+	width: usize,
+	height: usize,
+	items: Vec<T>,
+}
+
+impl<T: Serialize> Serialize for Grid<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut state = serializer.serialize_struct("Grid", 3)?;
+		state.serialize_field("width", &self.width())?;
+		state.serialize_field("height", &self.height())?;
+		state.serialize_field("items", self.as_slice())?;
+		state.end()
+	}
+}
+
+impl<'de, T: Deserialize<'de>> Deserialize<'de> for Grid<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let GridRepr { width, height, items } = GridRepr::deserialize(deserializer)?;
+		let len = items.len();
+		Grid::from_vec(width, height, items).ok_or_else(|| D::Error::custom(format_args!("{len} items don't fit {width}x{height} grid")))
+	}
+}
+
+/// serialized as words of [BitSet::as_words] so size of output is bounded by size of input
+impl Serialize for BitSet {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		self.as_words().serialize(serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for BitSet {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Vec::deserialize(deserializer).map(BitSet::from_words)
+	}
+}
+
+impl<T: Serialize> Serialize for PersistentVec<T> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.iter())
+	}
+}
+
+impl<'de, T: Deserialize<'de> + Clone> Deserialize<'de> for PersistentVec<T> {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		Vec::deserialize(deserializer).map(PersistentVec::from)
+	}
+}
+
+/// serialized as sequence of `(range, value)` ordered by start
+impl<K: Serialize + Ord + Clone, V: Serialize> Serialize for IntervalMap<K, V> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.iter())
+	}
+}
+
+/// overlapping ranges are rejected instead of overwritten
+impl<'de, K, V> Deserialize<'de> for IntervalMap<K, V>
+	where K: Deserialize<'de> + Ord + Clone,
+	      V: Deserialize<'de> + Clone + PartialEq {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let mut map = IntervalMap::new();
+		for (range, value) in Vec::<(Range<K>, V)>::deserialize(deserializer)? {
+			if map.overlapping(range.clone()).next().is_some() {
+				return Err(D::Error::custom("overlapping ranges"));
+			}
+			map.insert(range, value);
+		}
+		Ok(map)
+	}
+}
+
+/// serialized as sequence of `(key, priority)` in pop order, so order of equal priorities is kept
+impl<K: Serialize + Hash + Eq + Clone, P: Serialize + Ord> Serialize for IndexedHeap<K, P> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_seq(self.iter_sorted())
+	}
+}
+
+/// duplicated keys are rejected
+impl<'de, K, P> Deserialize<'de> for IndexedHeap<K, P>
+	where K: Deserialize<'de> + Hash + Eq + Clone,
+	      P: Deserialize<'de> + Ord {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let entries = Vec::<(K, P)>::deserialize(deserializer)?;
+		let mut heap = IndexedHeap::with_capacity(entries.len());
+		for (key, priority) in entries {
+			if heap.push(key, priority).is_some() {
+				return Err(D::Error::custom("duplicated key"));
+			}
+		}
+		Ok(heap)
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::{BitSet, ByteRing, CircularArray, CircularVec, Grid, IndexedHeap, IntervalMap, PersistentVec, Truncate};
+
+	#[test]
+	fn test_serde() {
+		let mut vec = CircularVec::new(3);
+		for i in 0..5 { vec.push(i); }
+		let json = serde_json::to_string(&vec).unwrap();
+		// logical order
+		assert_eq!(json, r#"{"capacity":3,"items":[2,3,4]}"#);
+		let arr = serde_json::from_str::<CircularArray<i32, 3>>(&json).unwrap();
+		assert_eq!(arr.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+		assert!(serde_json::from_str::<CircularArray<i32, 4>>(&json).is_err());
+
+		let json = r#"{"capacity":2,"items":[104,101,121]}"#;
+		assert!(serde_json::from_str::<ByteRing>(json).is_err());
+		let Truncate(ring) = serde_json::from_str::<Truncate<ByteRing>>(json).unwrap();
+		assert_eq!(ring.as_slices(), (&b"ey"[..], &b""[..]));
+
+		// memory is allocated by number of items instead of declared capacity
+		let json = format!(r#"{{"capacity":{},"items":[1,2]}}"#, usize::MAX);
+		let vec = serde_json::from_str::<CircularVec<u64>>(&json).unwrap();
+		assert_eq!((vec.capacity(), vec.len()), (usize::MAX, 2));
+		let mut ring = serde_json::from_str::<ByteRing>(&json).unwrap();
+		assert_eq!((ring.capacity(), ring.len()), (usize::MAX, 2));
+		ring.write_overwrite(b"abc");
+		assert_eq!(ring.as_slices(), (&[1, 2, b'a', b'b', b'c'][..], &b""[..]));
+	}
+
+	#[test]
+	fn test_serde_collections() {
+		let grid = Grid::new(2, 3, |x, y| x + y * 2);
+		let json = serde_json::to_string(&grid).unwrap();
+		assert_eq!(json, r#"{"width":2,"height":3,"items":[0,1,2,3,4,5]}"#);
+		assert_eq!(serde_json::from_str::<Grid<usize>>(&json).unwrap(), grid);
+		assert!(serde_json::from_str::<Grid<usize>>(r#"{"width":2,"height":2,"items":[0]}"#).is_err());
+
+		let set = [1, 64, 130].into_iter().collect::<BitSet>();
+		let json = serde_json::to_string(&set).unwrap();
+		assert_eq!(serde_json::from_str::<BitSet>(&json).unwrap().iter().collect::<Vec<_>>(), vec![1, 64, 130]);
+
+		let vec = (0..100).collect::<PersistentVec<_>>();
+		let json = serde_json::to_string(&vec).unwrap();
+		assert!(serde_json::from_str::<PersistentVec<i32>>(&json).unwrap().iter().eq(vec.iter()));
+
+		let map = [(0..5, "a"), (10..20, "b")].into_iter().collect::<IntervalMap<_, _>>();
+		let json = serde_json::to_string(&map).unwrap();
+		assert_eq!(json, r#"[[{"start":0,"end":5},"a"],[{"start":10,"end":20},"b"]]"#);
+		assert!(serde_json::from_str::<IntervalMap<i32, String>>(&json).unwrap().iter().map(|(range, it)| (range, it.as_str())).eq(map.iter().map(|(range, it)| (range, *it))));
+		assert!(serde_json::from_str::<IntervalMap<i32, String>>(r#"[[{"start":0,"end":5},"a"],[{"start":4,"end":6},"b"]]"#).is_err());
+
+		let mut heap = IndexedHeap::new();
+		for (key, priority) in [("a", 2), ("b", 1), ("c", 2), ("d", 1)] { heap.push(key.to_string(), priority); }
+		let json = serde_json::to_string(&heap).unwrap();
+		assert_eq!(json, r#"[["b",1],["d",1],["a",2],["c",2]]"#);
+		let mut heap = serde_json::from_str::<IndexedHeap<String, i32>>(&json).unwrap();
+		assert_eq!(heap.pop(), Some(("b".to_string(), 1)));
+		assert_eq!(heap.pop(), Some(("d".to_string(), 1)));
+		assert!(serde_json::from_str::<IndexedHeap<String, i32>>(r#"[["a",1],["a",2]]"#).is_err());
+	}
+}
@@ -0,0 +1,380 @@
+use std::ops::{Index, IndexMut};
+
+const NONE: u32 = u32::MAX;
+
+/// Generational key of [SlotMap]; key of removed value will never match value that reuse its slot
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct SlotKey {
+	idx: u32,
+	/// odd version means slot is occupied
+	version: u32,
+}
+
+impl SlotKey {
+	/// Get index of slot
+	pub fn index(&self) -> usize {
+		self.idx as usize
+	}
+
+	/// Get generation of slot
+	pub fn version(&self) -> u32 {
+		self.version
+	}
+}
+
+struct Slot {
+	version: u32,
+	/// index of value when occupied or next free slot when vacant
+	idx: u32,
+}
+
+/// Collection that store values densely and return generational [SlotKey] on insertion
+/// + insert, remove and lookup are O(1)
+/// + iteration is as fast as iterating [Vec]; order of values is changed on removal
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::SlotMap;
+/// let mut map = SlotMap::new();
+/// let a = map.insert("a");
+/// assert_eq!(map.remove(a), Some("a"));
+/// let b = map.insert("b");
+/// // stale key doesn't point to reused slot
+/// assert_eq!(map.get(a), None);
+/// assert_eq!(map[b], "b");
+/// ```
+pub struct SlotMap<T> {
+	slots: Vec<Slot>,
+	values: Vec<T>,
+	/// key of each value in `values`
+	keys: Vec<SlotKey>,
+	free_head: u32,
+}
+
+impl<T> Default for SlotMap<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> SlotMap<T> {
+	/// Create new empty slot map
+	pub fn new() -> Self {
+		Self::with_capacity(0)
+	}
+
+	/// Create new slot map that can hold `capacity` values without reallocation
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self {
+			slots: Vec::with_capacity(capacity),
+			values: Vec::with_capacity(capacity),
+			keys: Vec::with_capacity(capacity),
+			free_head: NONE,
+		}
+	}
+
+	/// Get number of values
+	pub fn len(&self) -> usize {
+		self.values.len()
+	}
+
+	/// Check if there is no value
+	pub fn is_empty(&self) -> bool {
+		self.values.is_empty()
+	}
+
+	/// Insert value and return its key
+	pub fn insert(&mut self, value: T) -> SlotKey {
+		self.insert_with_key(|_| value)
+	}
+
+	/// Insert value that need to know its own key
+	pub fn insert_with_key(&mut self, f: impl FnOnce(SlotKey) -> T) -> SlotKey {
+		let pos = self.values.len() as u32;
+		let key = if self.free_head != NONE {
+			let idx = self.free_head;
+			let slot = &mut self.slots[idx as usize];
+			self.free_head = slot.idx;
+			SlotKey { idx, version: slot.version | 1 }
+		} else {
+			assert!(self.slots.len() < NONE as usize, "slot map is full");
+			self.slots.push(Slot { version: 0, idx: NONE });
+			SlotKey { idx: self.slots.len() as u32 - 1, version: 1 }
+		};
+		self.values.push(f(key));
+		self.keys.push(key);
+		self.slots[key.idx as usize] = Slot { version: key.version, idx: pos };
+		key
+	}
+
+	/// get index of value in `values`
+	fn position(&self, key: SlotKey) -> Option<usize> {
+		let slot = self.slots.get(key.idx as usize)?;
+		if slot.version == key.version { Some(slot.idx as usize) } else { None }
+	}
+
+	/// Check if `key` still point to value
+	pub fn contains_key(&self, key: SlotKey) -> bool {
+		self.position(key).is_some()
+	}
+
+	/// Get value of `key`
+	pub fn get(&self, key: SlotKey) -> Option<&T> {
+		self.position(key).map(|it| &self.values[it])
+	}
+
+	/// Get mutable value of `key`
+	pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut T> {
+		self.position(key).map(|it| &mut self.values[it])
+	}
+
+	/// Remove value of `key`; `key` will be invalidated
+	pub fn remove(&mut self, key: SlotKey) -> Option<T> {
+		let pos = self.position(key)?;
+		let slot = &mut self.slots[key.idx as usize];
+		slot.version = slot.version.wrapping_add(1);
+		slot.idx = self.free_head;
+		self.free_head = key.idx;
+		self.keys.swap_remove(pos);
+		// update slot of value that was moved into removed position
+		if let Some(moved) = self.keys.get(pos) {
+			self.slots[moved.idx as usize].idx = pos as u32;
+		}
+		Some(self.values.swap_remove(pos))
+	}
+
+	/// Keep only values that `f(key, value)` return true
+	pub fn retain(&mut self, mut f: impl FnMut(SlotKey, &mut T) -> bool) {
+		let mut pos = 0;
+		while pos < self.values.len() {
+			let key = self.keys[pos];
+			if f(key, &mut self.values[pos]) {
+				pos += 1;
+			} else {
+				self.remove(key);
+			}
+		}
+	}
+
+	/// Remove all values; every key will be invalidated
+	pub fn clear(&mut self) {
+		for key in self.keys.drain(..) {
+			let slot = &mut self.slots[key.idx as usize];
+			slot.version = slot.version.wrapping_add(1);
+			slot.idx = self.free_head;
+			self.free_head = key.idx;
+		}
+		self.values.clear();
+	}
+
+	/// Iterate keys and values
+	pub fn iter(&self) -> impl Iterator<Item=(SlotKey, &T)> {
+		self.keys.iter().copied().zip(self.values.iter())
+	}
+
+	/// Iterate keys and mutable values
+	pub fn iter_mut(&mut self) -> impl Iterator<Item=(SlotKey, &mut T)> {
+		self.keys.iter().copied().zip(self.values.iter_mut())
+	}
+
+	/// Iterate keys
+	pub fn keys(&self) -> impl Iterator<Item=SlotKey> + '_ {
+		self.keys.iter().copied()
+	}
+
+	/// Get values as slice
+	pub fn values(&self) -> &[T] {
+		&self.values
+	}
+
+	/// Get values as mutable slice
+	pub fn values_mut(&mut self) -> &mut [T] {
+		&mut self.values
+	}
+}
+
+impl<T> Index<SlotKey> for SlotMap<T> {
+	type Output = T;
+
+	fn index(&self, key: SlotKey) -> &Self::Output {
+		self.get(key).expect("invalid slot map key")
+	}
+}
+
+impl<T> IndexMut<SlotKey> for SlotMap<T> {
+	fn index_mut(&mut self, key: SlotKey) -> &mut Self::Output {
+		self.get_mut(key).expect("invalid slot map key")
+	}
+}
+
+/// Map that associate extra data with keys of [SlotMap]
+/// value of stale key will be replaced when newer key of same slot is inserted
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::{SecondaryMap, SlotMap};
+/// let mut procs = SlotMap::new();
+/// let mut names = SecondaryMap::new();
+/// let key = procs.insert(1234);
+/// names.insert(key, "worker");
+/// assert_eq!(names[key], "worker");
+/// ```
+pub struct SecondaryMap<T> {
+	slots: Vec<Option<(u32, T)>>,
+	len: usize,
+}
+
+impl<T> Default for SecondaryMap<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> SecondaryMap<T> {
+	/// Create new empty secondary map
+	pub fn new() -> Self {
+		Self { slots: Vec::new(), len: 0 }
+	}
+
+	/// Get number of values
+	pub fn len(&self) -> usize {
+		self.len
+	}
+
+	/// Check if there is no value
+	pub fn is_empty(&self) -> bool {
+		self.len == 0
+	}
+
+	/// Insert value of `key` and return old value of same key
+	/// value will not be inserted if map already contains newer key of same slot
+	pub fn insert(&mut self, key: SlotKey, value: T) -> Option<T> {
+		let idx = key.idx as usize;
+		if idx >= self.slots.len() {
+			self.slots.resize_with(idx + 1, || None);
+		}
+		match &mut self.slots[idx] {
+			Some((version, old)) if *version == key.version => Some(std::mem::replace(old, value)),
+			// stored key is newer than given key
+			Some((version, _)) if version.wrapping_sub(key.version) < u32::MAX / 2 => None,
+			slot => {
+				if slot.is_none() { self.len += 1; }
+				*slot = Some((key.version, value));
+				None
+			}
+		}
+	}
+
+	/// Remove value of `key`
+	pub fn remove(&mut self, key: SlotKey) -> Option<T> {
+		let slot = self.slots.get_mut(key.idx as usize)?;
+		match slot {
+			Some((version, _)) if *version == key.version => {
+				self.len -= 1;
+				slot.take().map(|(_, it)| it)
+			}
+			_ => None,
+		}
+	}
+
+	/// Check if map contains value of `key`
+	pub fn contains_key(&self, key: SlotKey) -> bool {
+		self.get(key).is_some()
+	}
+
+	/// Get value of `key`
+	pub fn get(&self, key: SlotKey) -> Option<&T> {
+		match self.slots.get(key.idx as usize)? {
+			Some((version, value)) if *version == key.version => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Get mutable value of `key`
+	pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut T> {
+		match self.slots.get_mut(key.idx as usize)? {
+			Some((version, value)) if *version == key.version => Some(value),
+			_ => None,
+		}
+	}
+
+	/// Keep only values that `f(key, value)` return true
+	pub fn retain(&mut self, mut f: impl FnMut(SlotKey, &mut T) -> bool) {
+		for (idx, slot) in self.slots.iter_mut().enumerate() {
+			if let Some((version, value)) = slot {
+				if !f(SlotKey { idx: idx as u32, version: *version }, value) {
+					*slot = None;
+					self.len -= 1;
+				}
+			}
+		}
+	}
+
+	/// Remove all values
+	pub fn clear(&mut self) {
+		self.slots.clear();
+		self.len = 0;
+	}
+
+	/// Iterate keys and values in order of slot
+	pub fn iter(&self) -> impl Iterator<Item=(SlotKey, &T)> {
+		self.slots.iter().enumerate()
+			.filter_map(|(idx, it)| it.as_ref().map(|(version, value)| (SlotKey { idx: idx as u32, version: *version }, value)))
+	}
+}
+
+impl<T> Index<SlotKey> for SecondaryMap<T> {
+	type Output = T;
+
+	fn index(&self, key: SlotKey) -> &Self::Output {
+		self.get(key).expect("invalid secondary map key")
+	}
+}
+
+impl<T> IndexMut<SlotKey> for SecondaryMap<T> {
+	fn index_mut(&mut self, key: SlotKey) -> &mut Self::Output {
+		self.get_mut(key).expect("invalid secondary map key")
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::{SecondaryMap, SlotMap};
+
+	#[test]
+	fn test_slot_map() {
+		let mut map = SlotMap::new();
+		let keys = (0..5).map(|it| map.insert(it)).collect::<Vec<_>>();
+		assert_eq!(map.remove(keys[1]), Some(1));
+		assert_eq!(map.remove(keys[1]), None);
+		// last value was moved into removed position
+		assert_eq!(map.values(), &[0, 4, 2, 3]);
+		assert_eq!(map[keys[4]], 4);
+		let reused = map.insert_with_key(|key| key.index() * 10);
+		assert_eq!(reused.index(), keys[1].index());
+		assert_ne!(reused, keys[1]);
+		assert_eq!(map[reused], 10);
+		map.retain(|_, it| *it % 2 == 0);
+		assert_eq!(map.iter().map(|(_, it)| *it).collect::<Vec<_>>(), vec![0, 4, 2, 10]);
+		assert!(!map.contains_key(keys[3]));
+		map.clear();
+		assert!(map.is_empty() && map.get(keys[0]).is_none());
+	}
+
+	#[test]
+	fn test_secondary_map() {
+		let mut map = SlotMap::new();
+		let mut secondary = SecondaryMap::new();
+		let old = map.insert(());
+		secondary.insert(old, "old");
+		map.remove(old);
+		let new = map.insert(());
+		assert!(secondary.get(new).is_none());
+		assert_eq!(secondary.insert(new, "new"), None);
+		// stale key can't replace newer value
+		assert_eq!(secondary.insert(old, "stale"), None);
+		assert_eq!(secondary.get(old), None);
+		assert_eq!(secondary[new], "new");
+		assert_eq!(secondary.len(), 1);
+		assert_eq!(secondary.remove(new), Some("new"));
+		assert!(secondary.is_empty());
+	}
+}
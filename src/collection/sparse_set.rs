@@ -0,0 +1,125 @@
+/// Set of small integer ids with O(1) insert, remove, lookup and clear
+/// + ids are stored densely and can be iterated as slice; order is changed on removal
+/// + memory usage is proportional to largest id
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::SparseSet;
+/// let mut occupied = SparseSet::with_capacity(16);
+/// occupied.insert(7);
+/// occupied.insert(3);
+/// assert!(occupied.contains(7));
+/// occupied.clear();
+/// assert!(!occupied.contains(7));
+/// ```
+#[derive(Clone, Default)]
+pub struct SparseSet {
+	dense: Vec<usize>,
+	/// position of id in `dense`; may contain garbage for ids that are not in this set
+	sparse: Vec<usize>,
+}
+
+impl SparseSet {
+	/// Create new empty set
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Create new empty set that can hold ids in `0..capacity` without reallocation
+	pub fn with_capacity(capacity: usize) -> Self {
+		Self {
+			dense: Vec::with_capacity(capacity),
+			sparse: vec![0; capacity],
+		}
+	}
+
+	/// Get number of ids
+	pub fn len(&self) -> usize {
+		self.dense.len()
+	}
+
+	/// Check if there is no id
+	pub fn is_empty(&self) -> bool {
+		self.dense.is_empty()
+	}
+
+	/// Check if `id` is in this set
+	#[inline]
+	pub fn contains(&self, id: usize) -> bool {
+		self.sparse.get(id).is_some_and(|pos| self.dense.get(*pos) == Some(&id))
+	}
+
+	/// Add `id` into this set; return false if it's already in this set
+	pub fn insert(&mut self, id: usize) -> bool {
+		if self.contains(id) {
+			return false;
+		}
+		if id >= self.sparse.len() {
+			self.sparse.resize(id + 1, 0);
+		}
+		self.sparse[id] = self.dense.len();
+		self.dense.push(id);
+		true
+	}
+
+	/// Remove `id` from this set; return false if it's not in this set
+	pub fn remove(&mut self, id: usize) -> bool {
+		if !self.contains(id) {
+			return false;
+		}
+		let pos = self.sparse[id];
+		self.dense.swap_remove(pos);
+		if let Some(moved) = self.dense.get(pos) {
+			self.sparse[*moved] = pos;
+		}
+		true
+	}
+
+	/// Remove every id in O(1)
+	pub fn clear(&mut self) {
+		self.dense.clear();
+	}
+
+	/// Get ids as slice
+	pub fn as_slice(&self) -> &[usize] {
+		&self.dense
+	}
+
+	/// Iterate ids
+	pub fn iter(&self) -> impl Iterator<Item=usize> + '_ {
+		self.dense.iter().copied()
+	}
+}
+
+impl Extend<usize> for SparseSet {
+	fn extend<I: IntoIterator<Item=usize>>(&mut self, iter: I) {
+		for it in iter { self.insert(it); }
+	}
+}
+
+impl FromIterator<usize> for SparseSet {
+	fn from_iter<I: IntoIterator<Item=usize>>(iter: I) -> Self {
+		let mut set = Self::new();
+		set.extend(iter);
+		set
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::SparseSet;
+
+	#[test]
+	fn test_sparse_set() {
+		let mut set = [5, 1, 9].into_iter().collect::<SparseSet>();
+		assert!(!set.insert(1));
+		assert!(set.remove(5));
+		assert!(!set.remove(5));
+		// last id was moved into removed position
+		assert_eq!(set.as_slice(), &[9, 1]);
+		assert!(set.contains(9) && !set.contains(5) && !set.contains(100));
+		set.clear();
+		assert!(set.is_empty() && !set.contains(9));
+		assert!(set.insert(1));
+		assert_eq!(set.iter().collect::<Vec<_>>(), vec![1]);
+	}
+}
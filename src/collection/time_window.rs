@@ -0,0 +1,183 @@
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Source of time for [TimeWindow]  
+/// closure `Fn() -> Instant` can be used as clock (useful for tests)
+pub trait Clock {
+	/// Get current time
+	fn now(&self) -> Instant;
+}
+
+/// Clock that use [Instant::now]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+	#[inline]
+	fn now(&self) -> Instant {
+		Instant::now()
+	}
+}
+
+impl<F: Fn() -> Instant> Clock for F {
+	#[inline]
+	fn now(&self) -> Instant {
+		self()
+	}
+}
+
+/// TimeWindow is used to store continuous stream of data and discard elements older than given duration
+/// ### Usage
+/// + store samples that arrive irregularly eg. load average of latest 15 seconds
+/// # Example
+/// ```rust
+/// use std::cell::Cell;
+/// use std::time::{Duration, Instant};
+/// use pedestal_rs::collection::TimeWindow;
+/// let now = Cell::new(Instant::now());
+/// let mut window = TimeWindow::with_clock(Duration::from_secs(15), || now.get());
+/// window.push(1);
+/// now.set(now.get() + Duration::from_secs(10));
+/// window.push(2);
+/// assert_eq!(window.iter().collect::<Vec<_>>(), vec![&1, &2]);
+/// now.set(now.get() + Duration::from_secs(10));
+/// assert_eq!(window.iter().collect::<Vec<_>>(), vec![&2]);
+/// ```
+pub struct TimeWindow<T, C = SystemClock> {
+	entries: VecDeque<(Instant, T)>,
+	window: Duration,
+	limit: Option<usize>,
+	clock: C,
+}
+
+impl<T> TimeWindow<T> {
+	/// Create new time window that keep elements younger than `window`
+	pub fn new(window: Duration) -> Self {
+		Self::with_clock(window, SystemClock)
+	}
+}
+
+impl<T, C: Clock> TimeWindow<T, C> {
+	/// Create new time window that use custom clock
+	pub fn with_clock(window: Duration, clock: C) -> Self {
+		Self {
+			entries: VecDeque::new(),
+			window,
+			limit: None,
+			clock,
+		}
+	}
+
+	/// Limit number of elements in this window; oldest element will be discarded when full
+	#[must_use]
+	pub fn with_limit(mut self, limit: usize) -> Self {
+		assert!(limit > 0);
+		self.limit = Some(limit);
+		self.enforce_limit();
+		self
+	}
+
+	/// Get duration of this window
+	pub fn window(&self) -> Duration {
+		self.window
+	}
+
+	#[inline]
+	fn cutoff(&self) -> Option<Instant> {
+		self.clock.now().checked_sub(self.window)
+	}
+
+	/// index of first element that still inside window
+	#[inline]
+	fn start(&self) -> usize {
+		match self.cutoff() {
+			Some(cutoff) => self.entries.partition_point(|(at, _)| *at < cutoff),
+			None => 0,
+		}
+	}
+
+	fn enforce_limit(&mut self) {
+		if let Some(limit) = self.limit {
+			while self.entries.len() > limit {
+				self.entries.pop_front();
+			}
+		}
+	}
+
+	/// Append value with current time and discard expired elements
+	pub fn push(&mut self, item: T) {
+		let now = self.clock.now();
+		self.evict();
+		self.entries.push_back((now, item));
+		self.enforce_limit();
+	}
+
+	/// Remove expired elements from this window and return number of removed elements
+	pub fn evict(&mut self) -> usize {
+		let start = self.start();
+		self.entries.drain(..start);
+		start
+	}
+
+	/// Try to remove oldest element that still inside window
+	pub fn pop(&mut self) -> Option<T> {
+		self.evict();
+		self.entries.pop_front().map(|(_, it)| it)
+	}
+
+	/// Get number of elements inside window
+	pub fn len(&self) -> usize {
+		self.entries.len() - self.start()
+	}
+
+	/// Check if this window is empty
+	pub fn is_empty(&self) -> bool {
+		self.len() == 0
+	}
+
+	/// Iterate elements inside window from oldest to newest
+	pub fn iter(&self) -> impl DoubleEndedIterator<Item=&T> + '_ {
+		self.iter_with_time().map(|(_, it)| it)
+	}
+
+	/// Iterate elements inside window with time it was pushed
+	pub fn iter_with_time(&self) -> impl DoubleEndedIterator<Item=(Instant, &T)> + '_ {
+		self.entries.range(self.start()..).map(|(at, it)| (*at, it))
+	}
+
+	/// Take all elements inside window into vec
+	pub fn take(&mut self) -> Vec<T> {
+		self.evict();
+		self.entries.drain(..).map(|(_, it)| it).collect()
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::cell::Cell;
+	use std::time::{Duration, Instant};
+
+	use crate::collection::TimeWindow;
+
+	#[test]
+	fn test_time_window() {
+		let now = Cell::new(Instant::now());
+		let tick = |secs| now.set(now.get() + Duration::from_secs(secs));
+		let mut window = TimeWindow::with_clock(Duration::from_secs(5), || now.get()).with_limit(3);
+		window.push(1);
+		tick(1);
+		window.push(2);
+		tick(1);
+		window.push(3);
+		window.push(4);
+		// limited by count
+		assert_eq!(window.iter().collect::<Vec<_>>(), vec![&2, &3, &4]);
+		tick(5);
+		// 2 is older than 5 seconds
+		assert_eq!(window.len(), 2);
+		assert_eq!(window.evict(), 1);
+		tick(10);
+		assert!(window.is_empty());
+		assert_eq!(window.pop(), None);
+	}
+}
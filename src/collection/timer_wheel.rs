@@ -0,0 +1,276 @@
+use crate::collection::{SlotKey, SlotMap};
+
+const SLOT_BITS: usize = 6;
+const SLOTS: usize = 1 << SLOT_BITS;
+const LEVELS: usize = 6;
+/// list of timers that are too far to fit in any level
+const OVERFLOW: usize = LEVELS * SLOTS;
+/// list of timers that was inserted with deadline that already passed
+const PENDING: usize = OVERFLOW + 1;
+
+struct Timer<T> {
+	deadline: u64,
+	value: T,
+	list: usize,
+	prev: Option<SlotKey>,
+	next: Option<SlotKey>,
+}
+
+/// Hashed hierarchical timing wheel
+/// + time is counted in ticks; duration of tick is chosen by user (eg. 10ms)
+/// + insert and cancel are O(1); advancing only visit ticks that have timers
+/// + 6 levels of 64 slots cover 2^36 ticks, farther timers are kept in overflow list
+/// # Example
+/// ```rust
+/// use pedestal_rs::collection::TimerWheel;
+/// let mut wheel = TimerWheel::new();
+/// let health_check = wheel.insert_after(100, "health check");
+/// wheel.insert_after(5000, "kill");
+/// wheel.cancel(health_check);
+/// assert!(wheel.advance(4999).is_empty());
+/// assert_eq!(wheel.next_expiration(), Some(5000));
+/// assert_eq!(wheel.advance(1), vec!["kill"]);
+/// ```
+pub struct TimerWheel<T> {
+	timers: SlotMap<Timer<T>>,
+	heads: Vec<Option<SlotKey>>,
+	/// bitmap of non-empty slots in each level
+	occupied: [u64; LEVELS],
+	now: u64,
+}
+
+impl<T> Default for TimerWheel<T> {
+	fn default() -> Self {
+		Self::new()
+	}
+}
+
+impl<T> TimerWheel<T> {
+	/// Create new timer wheel that start at tick 0
+	pub fn new() -> Self {
+		Self::with_start(0)
+	}
+
+	/// Create new timer wheel that start at given tick
+	pub fn with_start(now: u64) -> Self {
+		Self {
+			timers: SlotMap::new(),
+			heads: vec![None; PENDING + 1],
+			occupied: [0; LEVELS],
+			now,
+		}
+	}
+
+	/// Get current tick
+	pub fn now(&self) -> u64 {
+		self.now
+	}
+
+	/// Get number of timers
+	pub fn len(&self) -> usize {
+		self.timers.len()
+	}
+
+	/// Check if there is no timer
+	pub fn is_empty(&self) -> bool {
+		self.timers.is_empty()
+	}
+
+	/// Insert timer that expire at `deadline` tick; timer with passed deadline will expire on next advance
+	pub fn insert(&mut self, deadline: u64, value: T) -> SlotKey {
+		let key = self.timers.insert(Timer { deadline, value, list: PENDING, prev: None, next: None });
+		self.link(key);
+		key
+	}
+
+	/// Insert timer that expire after `ticks` from now
+	pub fn insert_after(&mut self, ticks: u64, value: T) -> SlotKey {
+		self.insert(self.now.saturating_add(ticks), value)
+	}
+
+	/// Cancel timer and return its value; return None if timer already expired
+	pub fn cancel(&mut self, key: SlotKey) -> Option<T> {
+		if !self.timers.contains_key(key) {
+			return None;
+		}
+		self.unlink(key);
+		self.timers.remove(key).map(|it| it.value)
+	}
+
+	/// Get deadline of timer
+	pub fn deadline(&self, key: SlotKey) -> Option<u64> {
+		self.timers.get(key).map(|it| it.deadline)
+	}
+
+	/// Get value of timer
+	pub fn get(&self, key: SlotKey) -> Option<&T> {
+		self.timers.get(key).map(|it| &it.value)
+	}
+
+	/// Get mutable value of timer
+	pub fn get_mut(&mut self, key: SlotKey) -> Option<&mut T> {
+		self.timers.get_mut(key).map(|it| &mut it.value)
+	}
+
+	/// Get earliest tick that wheel need to be advanced to; timer may not expire at that tick
+	/// because timers in higher levels are only moved to lower levels
+	/// useful to decide how long to sleep before next [TimerWheel::advance_to]
+	pub fn next_expiration(&self) -> Option<u64> {
+		if self.heads[PENDING].is_some() {
+			return Some(self.now);
+		}
+		let mut next = None;
+		for (level, occupied) in self.occupied.iter().enumerate() {
+			let shift = level * SLOT_BITS;
+			let current = (self.now >> shift) & (SLOTS as u64 - 1);
+			// slots after current slot
+			let occupied = occupied & !((1u64 << current << 1).wrapping_sub(1));
+			if occupied != 0 {
+				let range = 1u64 << (shift + SLOT_BITS);
+				let tick = (self.now & !(range - 1)) + ((occupied.trailing_zeros() as u64) << shift);
+				next = Some(next.map_or(tick, |it: u64| it.min(tick)));
+				// timers in higher levels can't expire before this
+				break;
+			}
+		}
+		if next.is_none() && self.heads[OVERFLOW].is_some() {
+			let range = 1u64 << (LEVELS * SLOT_BITS);
+			next = Some((self.now & !(range - 1)).saturating_add(range));
+		}
+		next
+	}
+
+	/// Advance wheel by `ticks` and return values of expired timers
+	pub fn advance(&mut self, ticks: u64) -> Vec<T> {
+		self.advance_to(self.now.saturating_add(ticks))
+	}
+
+	/// Advance wheel to `tick` and return values of expired timers
+	pub fn advance_to(&mut self, tick: u64) -> Vec<T> {
+		let mut expired = Vec::new();
+		self.expire(PENDING, &mut expired);
+		while let Some(next) = self.next_expiration() {
+			if next > tick {
+				break;
+			}
+			self.now = next;
+			if next & ((1 << (LEVELS * SLOT_BITS)) - 1) == 0 {
+				self.expire(OVERFLOW, &mut expired);
+			}
+			for level in (0..LEVELS).rev() {
+				let shift = level * SLOT_BITS;
+				if next & ((1 << shift) - 1) == 0 {
+					let slot = (next >> shift) as usize & (SLOTS - 1);
+					self.expire(level * SLOTS + slot, &mut expired);
+				}
+			}
+		}
+		self.now = self.now.max(tick);
+		expired
+	}
+
+	/// take every timer in `list`; expired timers will be removed, others will be moved to lower level
+	fn expire(&mut self, list: usize, expired: &mut Vec<T>) {
+		let mut next = self.heads[list].take();
+		if list < OVERFLOW {
+			self.occupied[list / SLOTS] &= !(1 << (list % SLOTS));
+		}
+		while let Some(key) = next {
+			let timer = &self.timers[key];
+			next = timer.next;
+			if timer.deadline <= self.now {
+				expired.extend(self.timers.remove(key).map(|it| it.value));
+			} else {
+				self.link(key);
+			}
+		}
+	}
+
+	/// find list that timer should be placed in
+	fn place(&self, deadline: u64) -> usize {
+		if deadline <= self.now {
+			return PENDING;
+		}
+		// highest different bit decide level
+		let masked = (deadline ^ self.now) | (SLOTS as u64 - 1);
+		let level = (63 - masked.leading_zeros() as usize) / SLOT_BITS;
+		if level >= LEVELS {
+			return OVERFLOW;
+		}
+		level * SLOTS + ((deadline >> (level * SLOT_BITS)) as usize & (SLOTS - 1))
+	}
+
+	/// push timer to head of list that it belongs to
+	fn link(&mut self, key: SlotKey) {
+		let list = self.place(self.timers[key].deadline);
+		let head = self.heads[list].replace(key);
+		if let Some(head) = head {
+			self.timers[head].prev = Some(key);
+		}
+		let timer = &mut self.timers[key];
+		timer.list = list;
+		timer.prev = None;
+		timer.next = head;
+		if list < OVERFLOW {
+			self.occupied[list / SLOTS] |= 1 << (list % SLOTS);
+		}
+	}
+
+	fn unlink(&mut self, key: SlotKey) {
+		let Timer { list, prev, next, .. } = self.timers[key];
+		match prev {
+			Some(prev) => { self.timers[prev].next = next; }
+			None => { self.heads[list] = next; }
+		}
+		if let Some(next) = next {
+			self.timers[next].prev = prev;
+		}
+		if list < OVERFLOW && self.heads[list].is_none() {
+			self.occupied[list / SLOTS] &= !(1 << (list % SLOTS));
+		}
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use crate::collection::TimerWheel;
+
+	#[test]
+	fn test_timer_wheel() {
+		let mut wheel = TimerWheel::with_start(1000);
+		let mut seed = 42u64;
+		let mut random = move || {
+			seed ^= seed << 13;
+			seed ^= seed >> 7;
+			seed ^= seed << 17;
+			seed
+		};
+		let mut keys = Vec::new();
+		for i in 0..2000 {
+			// up to 2^40 ticks to test overflow list
+			let deadline = 1000 + (random() >> (24 + i % 40));
+			keys.push(wheel.insert(deadline, deadline));
+		}
+		// deadline that already passed
+		wheel.insert(10, 10);
+		let mut cancelled = 0;
+		for key in keys.iter().step_by(5) {
+			if wheel.cancel(*key).is_some() { cancelled += 1; }
+		}
+		assert!(wheel.cancel(keys[0]).is_none());
+		let mut fired = 0;
+		while !wheel.is_empty() {
+			let step = random() >> (random() % 64);
+			let before = wheel.now();
+			for deadline in wheel.advance(step) {
+				assert!(deadline <= wheel.now());
+				assert!(deadline > before || deadline <= 1000);
+				fired += 1;
+			}
+			for key in &keys {
+				if let Some(deadline) = wheel.deadline(*key) { assert!(deadline > wheel.now()); }
+			}
+		}
+		assert_eq!(fired + cancelled, 2001);
+	}
+}
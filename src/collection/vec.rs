@@ -1,22 +1,118 @@
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
+
 /// Create new vec with given size and use result from `init()` as default value  
-/// this macro existed because vec![] won't work on `!Clone` value
+/// this macro existed because vec![] won't work on `!Clone` value  
+/// use `|i| expr` form to initialize value from its index
+/// # Example
+/// ```rust
+/// use pedestal_rs::new_vec;
+/// assert_eq!(new_vec!(|i| i * 2; 3), vec![0, 2, 4]);
+/// ```
 #[macro_export]
 macro_rules! new_vec {
+    (|$i:pat_param| $init:expr; $size:expr) => {
+	    {
+		    let size = $size;
+		    let mut data = $crate::__Vec::with_capacity(size);
+			for $i in 0..size { data.push($init); }
+			data
+	    }
+    };
     ($init:expr; $size:expr) => {
 	    {
 		    let size = $size;
-		    let mut data = Vec::with_capacity(size);
+		    let mut data = $crate::__Vec::with_capacity(size);
 			for _ in 0..size { data.push($init); }
 			data
 	    }
     };
 }
 
-#[cfg(test)]
+/// Same as [new_vec] but `init` return [Result]; stop at first error and return `Result<Vec<T>, E>`
+/// # Example
+/// ```rust
+/// use pedestal_rs::try_new_vec;
+/// let data: Result<Vec<u8>, _> = try_new_vec!(|i| u8::try_from(i * 200); 2);
+/// assert_eq!(data, Ok(vec![0, 200]));
+/// let data: Result<Vec<u8>, _> = try_new_vec!(|i| u8::try_from(i * 200); 3);
+/// assert!(data.is_err());
+/// ```
+#[macro_export]
+macro_rules! try_new_vec {
+    (|$i:pat_param| $init:expr; $size:expr) => {
+	    {
+		    let size = $size;
+		    let mut data = $crate::__Vec::with_capacity(size);
+		    let mut result = Ok(());
+			for $i in 0..size {
+				match $init {
+					Ok(it) => { data.push(it); }
+					Err(e) => {
+						result = Err(e);
+						break;
+					}
+				}
+			}
+			result.map(|_| data)
+	    }
+    };
+    ($init:expr; $size:expr) => {
+	    $crate::try_new_vec!(|_| $init; $size)
+    };
+}
+
+/// Create new vec with given size and use result from `init(index)` as value
+/// unlike [new_vec], allocation failure (eg. huge size) will return error instead of abort
+/// # Example
+/// ```rust
+/// use std::collections::TryReserveError;
+/// use pedestal_rs::collection::vec::from_fn_fallible;
+/// let data = from_fn_fallible(3, |i| Ok::<_, TryReserveError>(i + 1));
+/// assert_eq!(data, Ok(vec![1, 2, 3]));
+/// assert!(from_fn_fallible(usize::MAX, |_| Ok::<u64, TryReserveError>(0)).is_err());
+/// ```
+pub fn from_fn_fallible<T, E>(size: usize, mut init: impl FnMut(usize) -> Result<T, E>) -> Result<Vec<T>, E>
+	where E: From<TryReserveError> {
+	let mut data = Vec::new();
+	data.try_reserve_exact(size)?;
+	for i in 0..size {
+		data.push(init(i)?);
+	}
+	Ok(data)
+}
+
+#[cfg(all(test, feature = "std"))]
 mod test {
+	use crate::collection::vec::from_fn_fallible;
+
 	#[test]
 	fn new_vec_test() {
 		let data = new_vec!(Option::<usize>::None; 4);
-		assert_eq!(data, vec![None, None, None, None])
+		assert_eq!(data, vec![None, None, None, None]);
+		let data = new_vec!(|i| Some(i); 3);
+		assert_eq!(data, vec![Some(0), Some(1), Some(2)]);
 	}
-}
\ No newline at end of file
+
+	#[test]
+	fn try_new_vec_test() {
+		let mut calls = 0;
+		let data: Result<Vec<usize>, usize> = try_new_vec!(|i| {
+			calls += 1;
+			if i < 2 { Ok(i) } else { Err(i) }
+		}; 5);
+		// stop at first error
+		assert_eq!(data, Err(2));
+		assert_eq!(calls, 3);
+		let data: Result<Vec<&str>, ()> = try_new_vec!(Ok("a"); 2);
+		assert_eq!(data, Ok(vec!["a", "a"]));
+	}
+
+	#[test]
+	fn from_fn_fallible_test() {
+		let data: Result<Vec<String>, Box<dyn std::error::Error>> = from_fn_fallible(usize::MAX / 2, |i| Ok(i.to_string()));
+		assert!(data.is_err());
+		let data: Result<Vec<usize>, Box<dyn std::error::Error>> = from_fn_fallible(2, Ok);
+		assert_eq!(data.unwrap(), vec![0, 1]);
+	}
+}
@@ -0,0 +1,266 @@
+use std::collections::VecDeque;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use crate::ext::AtomicArc;
+
+/// FIFO queue of writers that are waiting for their turn
+#[derive(Default)]
+struct Queue {
+	busy: bool,
+	next_id: u64,
+	waiters: VecDeque<(u64, Waker)>,
+}
+
+impl Queue {
+	fn wake_front(&self) {
+		if let Some((_, waker)) = self.waiters.front() {
+			waker.wake_by_ref();
+		}
+	}
+}
+
+/// Shared state that is modified by async closures without losing updates
+/// + [AsyncState::modify] is serialized; modifications are applied one by one in call order against latest value
+/// + [AsyncState::modify_optimistic] run without waiting and retry when value was committed by other writer meanwhile
+/// + [AsyncState::load] is wait-free and always return consistent snapshot, even while modification is running
+/// # Example
+/// ```rust
+/// use futures::executor::block_on;
+/// use futures::future::join;
+/// use pedestal_rs::ext::AsyncState;
+/// let state = AsyncState::new(vec![0]);
+/// block_on(join(
+///     state.modify(|mut it| async move { it.push(1); it }),
+///     state.modify(|mut it| async move { it.push(2); it }),
+/// ));
+/// assert_eq!(*state.load(), vec![0, 1, 2]);
+/// assert_eq!(state.version(), 2);
+/// ```
+pub struct AsyncState<T> {
+	value: AtomicArc<T>,
+	version: AtomicU64,
+	queue: Mutex<Queue>,
+}
+
+impl<T> AsyncState<T> {
+	/// Create new state
+	pub fn new(value: T) -> Self {
+		Self::from_arc(Arc::new(value))
+	}
+
+	/// Create new state from existing `Arc`
+	pub fn from_arc(value: Arc<T>) -> Self {
+		Self {
+			value: AtomicArc::from_arc(value),
+			version: AtomicU64::new(0),
+			queue: Mutex::new(Queue::default()),
+		}
+	}
+
+	/// Get current snapshot without waiting for running modification
+	pub fn load(&self) -> Arc<T> {
+		self.value.load()
+	}
+
+	/// Get number of committed modifications
+	pub fn version(&self) -> u64 {
+		self.version.load(Ordering::Acquire)
+	}
+
+	/// wait until every earlier writer is done
+	fn turn(&self) -> Acquire<'_> {
+		Acquire { queue: &self.queue, id: None }
+	}
+
+	/// must be called while holding turn
+	fn commit(&self, value: Arc<T>) -> Arc<T> {
+		let old = self.value.swap(value);
+		self.version.fetch_add(1, Ordering::AcqRel);
+		old
+	}
+}
+
+impl<T: Clone> AsyncState<T> {
+	/// Replace value after earlier modifications are done; return old value
+	pub async fn set(&self, value: T) -> Arc<T> {
+		let _turn = self.turn().await;
+		self.commit(Arc::new(value))
+	}
+
+	/// Wait for earlier modifications then modify clone of latest value; return old value
+	/// later modifications wait until `f` is done; dropping returned future before it's done discard modification
+	pub async fn modify<F, Fut>(&self, f: F) -> Arc<T>
+		where F: FnOnce(T) -> Fut,
+		      Fut: Future<Output=T> {
+		let _turn = self.turn().await;
+		let new = f(T::clone(&self.load())).await;
+		self.commit(Arc::new(new))
+	}
+
+	/// Same as [AsyncState::modify] but nothing is committed when `f` return error
+	pub async fn try_modify<F, Fut, E>(&self, f: F) -> Result<Arc<T>, E>
+		where F: FnOnce(T) -> Fut,
+		      Fut: Future<Output=Result<T, E>> {
+		let _turn = self.turn().await;
+		let new = f(T::clone(&self.load())).await?;
+		Ok(self.commit(Arc::new(new)))
+	}
+
+	/// Modify clone of current value without waiting for other writers;
+	/// if value was committed while `f` is running, `f` will be called again with latest value
+	/// # Example
+	/// ```rust
+	/// use futures::executor::block_on;
+	/// use futures::FutureExt;
+	/// use pedestal_rs::ext::AsyncState;
+	/// let state = AsyncState::new(0);
+	/// let mut calls = 0;
+	/// block_on(state.modify_optimistic(|it| {
+	///     calls += 1;
+	///     if calls == 1 {
+	///         // other writer commit while this one is running
+	///         state.set(10).now_or_never().unwrap();
+	///     }
+	///     async move { it + 1 }
+	/// }));
+	/// assert_eq!(calls, 2);
+	/// assert_eq!(*state.load(), 11);
+	/// ```
+	pub async fn modify_optimistic<F, Fut>(&self, mut f: F) -> Arc<T>
+		where F: FnMut(T) -> Fut,
+		      Fut: Future<Output=T> {
+		loop {
+			// version is read before value, so commit between them only cause retry
+			let version = self.version();
+			let new = f(T::clone(&self.load())).await;
+			let _turn = self.turn().await;
+			if self.version() == version {
+				return self.commit(Arc::new(new));
+			}
+		}
+	}
+}
+
+impl<T: Default> Default for AsyncState<T> {
+	fn default() -> Self {
+		Self::new(T::default())
+	}
+}
+
+fn lock(queue: &Mutex<Queue>) -> MutexGuard<'_, Queue> {
+	queue.lock().unwrap_or_else(|it| it.into_inner())
+}
+
+/// Future that resolve when every earlier writer is done
+struct Acquire<'a> {
+	queue: &'a Mutex<Queue>,
+	/// id in queue; None if not queued yet
+	id: Option<u64>,
+}
+
+impl<'a> Future for Acquire<'a> {
+	type Output = Turn<'a>;
+
+	fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let this = self.get_mut();
+		let mut queue = lock(this.queue);
+		match this.id {
+			None if !queue.busy && queue.waiters.is_empty() => {
+				queue.busy = true;
+				Poll::Ready(Turn(this.queue))
+			}
+			None => {
+				let id = queue.next_id;
+				queue.next_id += 1;
+				queue.waiters.push_back((id, cx.waker().clone()));
+				this.id = Some(id);
+				Poll::Pending
+			}
+			Some(id) if !queue.busy && queue.waiters.front().is_some_and(|it| it.0 == id) => {
+				queue.waiters.pop_front();
+				queue.busy = true;
+				this.id = None;
+				Poll::Ready(Turn(this.queue))
+			}
+			Some(id) => {
+				if let Some((_, waker)) = queue.waiters.iter_mut().find(|it| it.0 == id) {
+					if !waker.will_wake(cx.waker()) {
+						*waker = cx.waker().clone();
+					}
+				}
+				Poll::Pending
+			}
+		}
+	}
+}
+
+impl Drop for Acquire<'_> {
+	fn drop(&mut self) {
+		if let Some(id) = self.id {
+			let mut queue = lock(self.queue);
+			queue.waiters.retain(|it| it.0 != id);
+			if !queue.busy {
+				queue.wake_front();
+			}
+		}
+	}
+}
+
+/// Permission to commit; next writer is woken when dropped
+struct Turn<'a>(&'a Mutex<Queue>);
+
+impl Drop for Turn<'_> {
+	fn drop(&mut self) {
+		let mut queue = lock(self.0);
+		queue.busy = false;
+		queue.wake_front();
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use crate::ext::AsyncState;
+
+	#[test]
+	fn test_async_state() {
+		let runtime = tokio::runtime::Runtime::new().unwrap();
+		let state = Arc::new(AsyncState::new((0, Vec::new())));
+		let handles = (0..8).map(|task| {
+			let state = Arc::clone(&state);
+			runtime.spawn(async move {
+				for _ in 0..50 {
+					if task % 2 == 0 {
+						state.modify(|mut it| async move {
+							tokio::task::yield_now().await;
+							it.0 += 1;
+							it.1.push(task);
+							it
+						}).await;
+					} else {
+						state.modify_optimistic(|mut it| async move {
+							tokio::task::yield_now().await;
+							it.0 += 1;
+							it.1.push(task);
+							it
+						}).await;
+					}
+					// readers never wait and see consistent snapshot
+					let snapshot = state.load();
+					assert_eq!(snapshot.0, snapshot.1.len());
+				}
+			})
+		}).collect::<Vec<_>>();
+		runtime.block_on(async {
+			for handle in handles { handle.await.unwrap(); }
+			assert_eq!(state.try_modify(|_| async { Err("rejected") }).await, Err("rejected"));
+		});
+		assert_eq!(state.load().0, 400);
+		assert_eq!(state.version(), 400);
+	}
+}
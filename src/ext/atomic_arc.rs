@@ -0,0 +1,251 @@
+use std::fmt::{Debug, Formatter};
+use std::marker::PhantomData;
+use std::sync::Arc;
+
+#[cfg(pedestal_loom)]
+use loom::sync::atomic::{AtomicPtr, AtomicUsize, Ordering::SeqCst};
+#[cfg(pedestal_loom)]
+use loom::sync::Mutex;
+#[cfg(pedestal_loom)]
+use loom::thread::yield_now;
+#[cfg(not(pedestal_loom))]
+use std::sync::atomic::{AtomicPtr, AtomicUsize, Ordering::SeqCst};
+#[cfg(not(pedestal_loom))]
+use std::sync::Mutex;
+#[cfg(not(pedestal_loom))]
+use std::thread::yield_now;
+
+use crate::ext::CloneExt;
+
+/// Atomic `Arc<T>` cell; concurrent version of [crate::ext::ArcExt::modify] that doesn't need `&mut`
+/// + [AtomicArc::load] is wait-free
+/// + writers wait until readers that may still see old pointer are done, so old value is never freed while being loaded
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use pedestal_rs::ext::AtomicArc;
+/// let config = AtomicArc::new("Hello".to_string());
+/// let snapshot = config.load();
+/// let old = config.modify(|it| it.push_str(" world"));
+/// assert!(Arc::ptr_eq(&old, &snapshot));
+/// assert_eq!(*config.load(), "Hello world");
+/// ```
+pub struct AtomicArc<T> {
+	ptr: AtomicPtr<T>,
+	/// number of readers that are loading pointer; indexed by parity of `epoch`
+	readers: [AtomicUsize; 2],
+	epoch: AtomicUsize,
+	/// serialize writers that are waiting for readers
+	reclaim: Mutex<()>,
+	_marker: PhantomData<Arc<T>>,
+}
+
+impl<T> AtomicArc<T> {
+	/// Create new cell that hold `value`
+	pub fn new(value: T) -> Self {
+		Self::from_arc(Arc::new(value))
+	}
+
+	/// Create new cell that hold `value`
+	pub fn from_arc(value: Arc<T>) -> Self {
+		Self {
+			ptr: AtomicPtr::new(Arc::into_raw(value).cast_mut()),
+			readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+			epoch: AtomicUsize::new(0),
+			reclaim: Mutex::new(()),
+			_marker: PhantomData,
+		}
+	}
+
+	/// Get current value
+	pub fn load(&self) -> Arc<T> {
+		let readers = &self.readers[self.epoch.load(SeqCst) & 1];
+		readers.fetch_add(1, SeqCst);
+		let ptr = self.ptr.load(SeqCst);
+		// SAFETY: writer can't release `ptr` until `readers` is decreased
+		unsafe { Arc::increment_strong_count(ptr) };
+		readers.fetch_sub(1, SeqCst);
+		unsafe { Arc::from_raw(ptr) }
+	}
+
+	/// Replace current value and return old value
+	pub fn swap(&self, value: Arc<T>) -> Arc<T> {
+		let old = self.ptr.swap(Arc::into_raw(value).cast_mut(), SeqCst);
+		self.wait_readers();
+		// SAFETY: no reader can see `old` anymore
+		unsafe { Arc::from_raw(old) }
+	}
+
+	/// Replace current value
+	pub fn store(&self, value: Arc<T>) {
+		drop(self.swap(value));
+	}
+
+	/// Replace value if current value is `current`; return old value on success or give back `new` on failure
+	pub fn compare_and_swap(&self, current: &Arc<T>, new: Arc<T>) -> Result<Arc<T>, Arc<T>> {
+		let new = Arc::into_raw(new).cast_mut();
+		match self.ptr.compare_exchange(Arc::as_ptr(current).cast_mut(), new, SeqCst, SeqCst) {
+			Ok(old) => {
+				self.wait_readers();
+				Ok(unsafe { Arc::from_raw(old) })
+			}
+			Err(_) => Err(unsafe { Arc::from_raw(new) }),
+		}
+	}
+
+	/// Read-copy-update; create new value from current value and retry when other writer replaced it meanwhile
+	/// `f` may be called more than once; return old value
+	pub fn rcu(&self, mut f: impl FnMut(&T) -> T) -> Arc<T> {
+		let mut current = self.load();
+		loop {
+			match self.compare_and_swap(&current, Arc::new(f(&current))) {
+				Ok(old) => return old,
+				Err(_) => { current = self.load(); }
+			}
+		}
+	}
+
+	/// Same as [AtomicArc::rcu] but modify clone of current value (same as [CloneExt::as_owned])
+	pub fn modify(&self, mut f: impl FnMut(&mut T)) -> Arc<T> where T: Clone {
+		self.rcu(|it| it.as_owned(&mut f))
+	}
+
+	/// wait until readers that started before this call are done
+	fn wait_readers(&self) {
+		let _guard = self.reclaim.lock().unwrap_or_else(|it| it.into_inner());
+		// readers may use stale epoch, so both counters need to be drained
+		for _ in 0..2 {
+			let readers = &self.readers[self.epoch.fetch_add(1, SeqCst) & 1];
+			// read-modify-write always observe latest count; plain load may miss increment of reader that loaded old pointer
+			while readers.fetch_add(0, SeqCst) != 0 {
+				yield_now();
+			}
+		}
+	}
+
+	/// Take value out of this cell
+	pub fn into_inner(self) -> Arc<T> {
+		self.load()
+	}
+}
+
+impl<T> Drop for AtomicArc<T> {
+	fn drop(&mut self) {
+		drop(unsafe { Arc::from_raw(self.ptr.load(SeqCst)) });
+	}
+}
+
+impl<T: Default> Default for AtomicArc<T> {
+	fn default() -> Self {
+		Self::new(T::default())
+	}
+}
+
+impl<T> From<Arc<T>> for AtomicArc<T> {
+	fn from(value: Arc<T>) -> Self {
+		Self::from_arc(value)
+	}
+}
+
+impl<T: Debug> Debug for AtomicArc<T> {
+	fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+		f.debug_tuple("AtomicArc").field(&self.load()).finish()
+	}
+}
+
+#[cfg(all(test, not(pedestal_loom)))]
+mod test {
+	use std::sync::Arc;
+
+	use crate::ext::AtomicArc;
+
+	#[test]
+	fn test_atomic_arc() {
+		let cell = Arc::new(AtomicArc::new(0usize));
+		let handles = (0..4).map(|_| {
+			let cell = Arc::clone(&cell);
+			std::thread::spawn(move || {
+				for _ in 0..1000 {
+					cell.rcu(|it| it + 1);
+					assert!(*cell.load() > 0);
+				}
+			})
+		}).collect::<Vec<_>>();
+		for handle in handles { handle.join().unwrap(); }
+		assert_eq!(*cell.load(), 4000);
+		let old = cell.load();
+		assert!(cell.compare_and_swap(&Arc::new(4000), Arc::new(0)).is_err());
+		assert!(Arc::ptr_eq(&cell.compare_and_swap(&old, Arc::new(0)).unwrap(), &old));
+		assert_eq!(Arc::strong_count(&old), 1);
+		let cell = Arc::into_inner(cell).unwrap();
+		assert_eq!(*cell.into_inner(), 0);
+	}
+}
+
+/// run with `RUSTFLAGS="--cfg pedestal_loom" cargo test --release --features mutation loom`
+#[cfg(all(test, pedestal_loom))]
+mod loom_test {
+	use std::sync::Arc;
+
+	use loom::sync::atomic::AtomicBool;
+	use loom::sync::atomic::Ordering::SeqCst;
+
+	use crate::ext::{ArcExt, AtomicArc};
+
+	/// value that detect use after drop
+	#[derive(Default)]
+	struct Guarded(AtomicBool);
+
+	impl Guarded {
+		fn check(&self) {
+			assert!(!self.0.load(SeqCst), "value was used after drop");
+		}
+	}
+
+	impl Drop for Guarded {
+		fn drop(&mut self) {
+			assert!(!self.0.swap(true, SeqCst), "value was dropped twice");
+		}
+	}
+
+	#[test]
+	fn loom_load_store() {
+		loom::model(|| {
+			let cell = Arc::new(AtomicArc::new(Guarded::default()));
+			let reader = {
+				let cell = Arc::clone(&cell);
+				loom::thread::spawn(move || cell.load().check())
+			};
+			cell.store(Arc::new(Guarded::default()));
+			cell.load().check();
+			reader.join().unwrap();
+		});
+	}
+
+	#[test]
+	fn loom_rcu() {
+		loom::model(|| {
+			let cell = Arc::new(AtomicArc::new(0));
+			let writer = {
+				let cell = Arc::clone(&cell);
+				loom::thread::spawn(move || { cell.rcu(|it| it + 1); })
+			};
+			cell.modify(|it| *it += 1);
+			writer.join().unwrap();
+			assert_eq!(*cell.load(), 2);
+		});
+	}
+
+	#[test]
+	fn loom_as_owned() {
+		loom::model(|| {
+			let cell = AtomicArc::new(vec![1]);
+			let snapshot = cell.load();
+			let copied = snapshot.as_owned_arc(|it| it.push(2));
+			let old = cell.modify(|it| it.push(2));
+			assert!(Arc::ptr_eq(&old, &snapshot));
+			assert_eq!(cell.load(), copied);
+			assert_eq!(*snapshot, vec![1]);
+		});
+	}
+}
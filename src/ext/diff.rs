@@ -0,0 +1,311 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Debug;
+use std::hash::{BuildHasher, Hash};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Compute change set between old and new value; see [crate::ext::ArcExt::modify_diff]
+/// + leaf types (numbers, `String`, `Option`, ...) are recorded as [Replace]
+/// + `Vec<T>` is recorded as insertions and removals (shortest edit script)
+/// + maps are recorded as [MapChange] of each key
+/// + `#[derive(Diff)]` (`derive` feature) generate `<Name>Change` with field-level changes
+/// # Example
+/// ```rust
+/// use std::collections::HashMap;
+/// use pedestal_rs::ext::{Diff, MapChange, Replace, VecChange};
+/// let old = vec!["a", "b", "c"];
+/// let new = vec!["a", "c", "d"];
+/// assert_eq!(old.diff(&new), Some(vec![
+///     VecChange::Remove { index: 1, value: "b" },
+///     VecChange::Insert { index: 2, value: "d" },
+/// ]));
+/// let old = HashMap::from([("port", 80)]);
+/// let new = HashMap::from([("port", 8080)]);
+/// assert_eq!(old.diff(&new), Some(vec![MapChange::Modify("port", Replace { old: 80, new: 8080 })]));
+/// assert!(old.diff(&old).is_none());
+/// ```
+pub trait Diff {
+	/// Change set of this type
+	type Change: Debug + Clone + PartialEq;
+
+	/// Get changes from `self` to `new`; return None if nothing was changed
+	fn diff(&self, new: &Self) -> Option<Self::Change>;
+}
+
+/// Value that was replaced as a whole
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Replace<T> {
+	pub old: T,
+	pub new: T,
+}
+
+impl<T: Clone + PartialEq> Replace<T> {
+	/// Create change if `old` and `new` are not equal; useful to implement [Diff] for leaf types
+	pub fn compare(old: &T, new: &T) -> Option<Self> {
+		if old == new { None } else { Some(Self { old: old.clone(), new: new.clone() }) }
+	}
+}
+
+/// Change of `Vec` element
+/// + index of `Remove` is position in old vec, index of `Insert` is position in new vec
+/// + applying every `Remove` in reverse order then every `Insert` in order turn old vec into new vec
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum VecChange<T> {
+	Insert { index: usize, value: T },
+	Remove { index: usize, value: T },
+}
+
+/// Change of map entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum MapChange<K, V: Diff> {
+	Insert(K, V),
+	Remove(K, V),
+	Modify(K, V::Change),
+}
+
+macro_rules! impl_replace {
+	($($ty:ty),*) => {
+		$(
+		impl Diff for $ty {
+			type Change = Replace<$ty>;
+
+			#[inline]
+			fn diff(&self, new: &Self) -> Option<Self::Change> {
+				Replace::compare(self, new)
+			}
+		}
+		)*
+	};
+}
+
+impl_replace!(bool, char, u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64,
+	String, &'static str, PathBuf, Duration, ());
+
+impl<T: Clone + Debug + PartialEq> Diff for Option<T> {
+	type Change = Replace<Option<T>>;
+
+	#[inline]
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		Replace::compare(self, new)
+	}
+}
+
+impl<T: Diff + ?Sized> Diff for Box<T> {
+	type Change = T::Change;
+
+	#[inline]
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		T::diff(self, new)
+	}
+}
+
+impl<T: Diff + ?Sized> Diff for Arc<T> {
+	type Change = T::Change;
+
+	/// same pointer is never compared
+	#[inline]
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		if Arc::ptr_eq(self, new) { None } else { T::diff(self, new) }
+	}
+}
+
+/// maximum number of edits [Diff] of `Vec` search for before recording whole range as replaced
+const MAX_VEC_EDITS: usize = 1024;
+
+/// Vec is compared by shortest edit script (Myers); O((n + m) * d) time and O(d^2) memory after common prefix and suffix are trimmed  
+/// if more than 1024 edits are needed, every remaining old element is removed and every new element is inserted
+impl<T: Clone + Debug + PartialEq> Diff for Vec<T> {
+	type Change = Vec<VecChange<T>>;
+
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		let prefix = self.iter().zip(new.iter()).take_while(|(a, b)| a == b).count();
+		let suffix = self[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(a, b)| a == b).count();
+		let old = &self[prefix..self.len() - suffix];
+		let new = &new[prefix..new.len() - suffix];
+		if old.is_empty() && new.is_empty() {
+			return None;
+		}
+		let changes = match shortest_edit(old, new, MAX_VEC_EDITS) {
+			Some(edits) => edits.into_iter().map(|(remove, index)| if remove {
+				VecChange::Remove { index: prefix + index, value: old[index].clone() }
+			} else {
+				VecChange::Insert { index: prefix + index, value: new[index].clone() }
+			}).collect(),
+			None => old.iter().enumerate().map(|(i, it)| VecChange::Remove { index: prefix + i, value: it.clone() })
+				.chain(new.iter().enumerate().map(|(i, it)| VecChange::Insert { index: prefix + i, value: it.clone() }))
+				.collect(),
+		};
+		Some(changes)
+	}
+}
+
+/// Myers' diff; return `(is_remove, index)` of each edit ordered by position, or None if more than `limit` edits are needed
+fn shortest_edit<T: PartialEq>(old: &[T], new: &[T], limit: usize) -> Option<Vec<(bool, usize)>> {
+	let (n, m) = (old.len() as isize, new.len() as isize);
+	let limit = limit.min(old.len() + new.len()) as isize;
+	// v[offset + k] = furthest x on diagonal k = x - y
+	let offset = limit + 1;
+	let mut v = vec![0isize; 2 * limit as usize + 3];
+	// trace[d][d + k] = v[offset + k] after step d
+	let mut trace = Vec::new();
+	for d in 0..=limit {
+		for k in (-d..=d).step_by(2) {
+			let mut x = if k == -d || (k != d && v[(offset + k - 1) as usize] < v[(offset + k + 1) as usize]) {
+				v[(offset + k + 1) as usize]
+			} else {
+				v[(offset + k - 1) as usize] + 1
+			};
+			let mut y = x - k;
+			while x < n && y < m && old[x as usize] == new[y as usize] {
+				x += 1;
+				y += 1;
+			}
+			v[(offset + k) as usize] = x;
+			if x >= n && y >= m {
+				return Some(backtrack(&trace, n, m, d));
+			}
+		}
+		trace.push(v[(offset - d) as usize..=(offset + d) as usize].to_vec());
+	}
+	None
+}
+
+/// walk back from `(n, m)` through furthest points of each step
+fn backtrack(trace: &[Vec<isize>], n: isize, m: isize, d: isize) -> Vec<(bool, usize)> {
+	let mut edits = Vec::with_capacity(d as usize);
+	let (mut x, mut y) = (n, m);
+	for d in (1..=d).rev() {
+		let prev = &trace[d as usize - 1];
+		let k = x - y;
+		let get = |k: isize| prev[(k + d - 1) as usize];
+		let insert = k == -d || (k != d && get(k - 1) < get(k + 1));
+		let prev_k = if insert { k + 1 } else { k - 1 };
+		let prev_x = get(prev_k);
+		let prev_y = prev_x - prev_k;
+		edits.push(if insert { (false, prev_y as usize) } else { (true, prev_x as usize) });
+		x = prev_x;
+		y = prev_y;
+	}
+	edits.reverse();
+	edits
+}
+
+/// changes are in arbitrary order
+impl<K, V, S> Diff for HashMap<K, V, S>
+	where K: Hash + Eq + Clone + Debug,
+	      V: Diff + Clone + Debug + PartialEq,
+	      S: BuildHasher {
+	type Change = Vec<MapChange<K, V>>;
+
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		let changes = diff_map(self.iter(), |it| new.get(it), new.iter().filter(|(it, _)| !self.contains_key(it)));
+		if changes.is_empty() { None } else { Some(changes) }
+	}
+}
+
+/// removed and modified entries come before inserted entries; both are ordered by key
+impl<K, V> Diff for BTreeMap<K, V>
+	where K: Ord + Clone + Debug,
+	      V: Diff + Clone + Debug + PartialEq {
+	type Change = Vec<MapChange<K, V>>;
+
+	fn diff(&self, new: &Self) -> Option<Self::Change> {
+		let changes = diff_map(self.iter(), |it| new.get(it), new.iter().filter(|(it, _)| !self.contains_key(it)));
+		if changes.is_empty() { None } else { Some(changes) }
+	}
+}
+
+fn diff_map<'a, K: Clone + 'a, V: Diff + Clone + 'a>(
+	old: impl Iterator<Item=(&'a K, &'a V)>,
+	get_new: impl Fn(&K) -> Option<&'a V>,
+	inserted: impl Iterator<Item=(&'a K, &'a V)>,
+) -> Vec<MapChange<K, V>> {
+	let mut changes = Vec::new();
+	for (key, value) in old {
+		match get_new(key) {
+			Some(new) => {
+				if let Some(change) = value.diff(new) {
+					changes.push(MapChange::Modify(key.clone(), change));
+				}
+			}
+			None => changes.push(MapChange::Remove(key.clone(), value.clone())),
+		}
+	}
+	changes.extend(inserted.map(|(key, value)| MapChange::Insert(key.clone(), value.clone())));
+	changes
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+	use std::collections::BTreeMap;
+	use std::sync::Arc;
+
+	use crate::ext::{ArcExt, Diff, MapChange, Replace, VecChange};
+
+	#[derive(Clone, Debug, PartialEq, Diff)]
+	enum Shape {
+		Circle { radius: u32 },
+		Rect(u32, #[diff(skip)] u32, u32),
+		Empty,
+	}
+
+	#[derive(Clone, Debug, PartialEq, Diff)]
+	struct Scene<T: Clone + std::fmt::Debug + PartialEq> {
+		shapes: BTreeMap<String, Shape>,
+		tags: Vec<T>,
+	}
+
+	#[test]
+	fn test_diff() {
+		let old = "ABCABBA".chars().collect::<Vec<_>>();
+		let new = "CBABAC".chars().collect::<Vec<_>>();
+		let changes = old.diff(&new).unwrap();
+		// LCS has length 4, so 3 removals and 2 insertions
+		assert_eq!(changes.len(), 5);
+		let mut patched = old.clone();
+		for change in changes.iter().rev() {
+			if let VecChange::Remove { index, .. } = change { patched.remove(*index); }
+		}
+		for change in &changes {
+			if let VecChange::Insert { index, value } = change { patched.insert(*index, *value); }
+		}
+		assert_eq!(patched, new);
+
+		// large vec with few edits doesn't allocate n * m table
+		let old = (0..100_000).collect::<Vec<_>>();
+		let mut new = old.clone();
+		new.remove(10);
+		new.insert(50_000, -1);
+		new.remove(99_000);
+		assert_eq!(old.diff(&new).unwrap(), vec![
+			VecChange::Remove { index: 10, value: 10 },
+			VecChange::Insert { index: 50_000, value: -1 },
+			VecChange::Remove { index: 99_000, value: 99_000 },
+		]);
+		// too many edits fall back to replacing whole changed range
+		let new = old.iter().map(|it| it * 2).collect::<Vec<_>>();
+		let changes = old.diff(&new).unwrap();
+		assert_eq!(changes.len(), 2 * 100_000 - 2);
+
+		let mut scene = Arc::new(Scene {
+			shapes: [("a".to_string(), Shape::Circle { radius: 1 }), ("b".to_string(), Shape::Rect(1, 2, 3))].into(),
+			tags: vec![1, 2],
+		});
+		let (old, change) = scene.modify_diff(|it| {
+			*it.shapes.get_mut("a").unwrap() = Shape::Circle { radius: 2 };
+			it.shapes.insert("b".to_string(), Shape::Rect(1, 5, 3));
+			it.shapes.insert("c".to_string(), Shape::Empty);
+		});
+		assert_eq!(old.shapes.len(), 2);
+		let change = change.unwrap();
+		assert_eq!(change.tags, None);
+		assert_eq!(change.shapes, Some(vec![
+			MapChange::Modify("a".to_string(), ShapeChange::Circle { radius: Some(Replace { old: 1, new: 2 }) }),
+			MapChange::Insert("c".to_string(), Shape::Empty),
+		]));
+		assert_eq!(Shape::Empty.diff(&Shape::Circle { radius: 1 }), Some(ShapeChange::Replaced(Replace { old: Shape::Empty, new: Shape::Circle { radius: 1 } })));
+		assert_eq!(Shape::Rect(1, 2, 3).diff(&Shape::Rect(1, 2, 4)), Some(ShapeChange::Rect(None, Some(Replace { old: 3, new: 4 }))));
+		assert!(scene.modify_diff(|_| {}).1.is_none());
+	}
+}
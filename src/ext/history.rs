@@ -0,0 +1,234 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::collection::{CircularVec, Clock, SystemClock};
+use crate::ext::ArcExt;
+
+/// Undo/redo history of `Arc<T>` snapshots
+/// + at most `depth` undo steps are kept; oldest step is dropped when full
+/// + modifications within squash interval (see [History::with_squash]) are merged into one undo step
+/// + snapshots share unchanged data when `T` does (eg. [crate::collection::PersistentVec]); otherwise `T` is cloned on every modification
+/// # Example
+/// ```rust
+/// use pedestal_rs::ext::History;
+/// let mut text = History::new(String::new(), 100);
+/// text.modify(|it| it.push_str("Hello"));
+/// text.modify(|it| it.push_str(" world"));
+/// assert_eq!(text.undo().map(|it| it.as_str()), Some("Hello"));
+/// assert_eq!(text.redo().map(|it| it.as_str()), Some("Hello world"));
+/// text.undo();
+/// // new modification discard redo steps
+/// text.modify(|it| it.push('!'));
+/// assert!(!text.can_redo());
+/// assert_eq!(**text.get(), "Hello!");
+/// ```
+pub struct History<T, C = SystemClock> {
+	current: Arc<T>,
+	undo: CircularVec<Arc<T>>,
+	redo: Vec<Arc<T>>,
+	clock: C,
+	squash: Option<Duration>,
+	/// time of last modification that can be squashed with next one
+	last_edit: Option<Instant>,
+}
+
+impl<T> History<T> {
+	/// Create new history that keep at most `depth` undo steps
+	/// # Panics
+	/// if `depth` is 0
+	pub fn new(value: T, depth: usize) -> Self {
+		Self::from_arc(Arc::new(value), depth)
+	}
+
+	/// Create new history from existing `Arc`
+	/// # Panics
+	/// if `depth` is 0
+	pub fn from_arc(value: Arc<T>, depth: usize) -> Self {
+		assert!(depth > 0, "depth must not be 0");
+		Self {
+			current: value,
+			undo: CircularVec::new(depth),
+			redo: Vec::new(),
+			clock: SystemClock,
+			squash: None,
+			last_edit: None,
+		}
+	}
+}
+
+impl<T, C: Clock> History<T, C> {
+	/// Use custom clock for squashing modifications
+	#[must_use]
+	pub fn with_clock<C2: Clock>(self, clock: C2) -> History<T, C2> {
+		History {
+			current: self.current,
+			undo: self.undo,
+			redo: self.redo,
+			clock,
+			squash: self.squash,
+			last_edit: None,
+		}
+	}
+
+	/// Merge modifications that happen within `interval` after previous one into single undo step
+	#[must_use]
+	pub fn with_squash(mut self, interval: Duration) -> Self {
+		self.squash = Some(interval);
+		self
+	}
+
+	/// Get current value
+	pub fn get(&self) -> &Arc<T> {
+		&self.current
+	}
+
+	/// Replace current value with `value` and record old value as undo step; return old value
+	pub fn set(&mut self, value: Arc<T>) -> Arc<T> {
+		let now = self.clock.now();
+		let squash = match (self.squash, self.last_edit) {
+			(Some(interval), Some(last)) => now.saturating_duration_since(last) < interval,
+			_ => false,
+		};
+		let old = std::mem::replace(&mut self.current, value);
+		if !squash {
+			self.undo.push(Arc::clone(&old));
+		}
+		self.redo.clear();
+		self.last_edit = Some(now);
+		old
+	}
+
+	/// Modify current value (same as [ArcExt::modify]) and record old value as undo step; return old value
+	pub fn modify(&mut self, f: impl FnOnce(&mut T)) -> Arc<T> where T: Clone + 'static {
+		let new = self.current.as_owned_arc(f);
+		self.set(new)
+	}
+
+	/// Same as [History::modify] but nothing is recorded when `f` return error
+	pub fn try_modify<E>(&mut self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Result<Arc<T>, E> where T: Clone + 'static {
+		let mut new = T::clone(&self.current);
+		f(&mut new)?;
+		Ok(self.set(Arc::new(new)))
+	}
+
+	/// Stop squashing; next modification always create new undo step
+	pub fn checkpoint(&mut self) {
+		self.last_edit = None;
+	}
+
+	/// Restore previous value and return it; return None if there is no undo step
+	pub fn undo(&mut self) -> Option<&Arc<T>> {
+		let prev = self.undo.pop_back()?;
+		self.redo.push(std::mem::replace(&mut self.current, prev));
+		self.last_edit = None;
+		Some(&self.current)
+	}
+
+	/// Restore value that was undone and return it; return None if there is no redo step
+	pub fn redo(&mut self) -> Option<&Arc<T>> {
+		let next = self.redo.pop()?;
+		self.undo.push(std::mem::replace(&mut self.current, next));
+		self.last_edit = None;
+		Some(&self.current)
+	}
+
+	/// Check if [History::undo] will succeed
+	pub fn can_undo(&self) -> bool {
+		!self.undo.is_empty()
+	}
+
+	/// Check if [History::redo] will succeed
+	pub fn can_redo(&self) -> bool {
+		!self.redo.is_empty()
+	}
+
+	/// Get number of undo steps
+	pub fn undo_len(&self) -> usize {
+		self.undo.len()
+	}
+
+	/// Get number of redo steps
+	pub fn redo_len(&self) -> usize {
+		self.redo.len()
+	}
+
+	/// Iterate undo snapshots from oldest to newest
+	pub fn undo_iter(&self) -> impl Iterator<Item=&Arc<T>> {
+		self.undo.iter()
+	}
+
+	/// Create independent history that start from current state; undo steps are shared, redo steps are not
+	/// modifications on either history doesn't affect the other
+	pub fn branch(&self) -> Self where C: Clone {
+		let mut undo = CircularVec::new(self.undo.capacity());
+		for it in self.undo.iter() {
+			undo.push(Arc::clone(it));
+		}
+		Self {
+			current: Arc::clone(&self.current),
+			undo,
+			redo: Vec::new(),
+			clock: self.clock.clone(),
+			squash: self.squash,
+			last_edit: None,
+		}
+	}
+
+	/// Drop every undo and redo step
+	pub fn clear(&mut self) {
+		self.undo.take();
+		self.redo.clear();
+		self.last_edit = None;
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::cell::Cell;
+	use std::sync::Arc;
+	use std::time::{Duration, Instant};
+
+	use crate::ext::History;
+
+	#[test]
+	fn test_history() {
+		let now = Cell::new(Instant::now());
+		let mut history = History::new(0, 3).with_clock(|| now.get()).with_squash(Duration::from_secs(1));
+		for _ in 0..5 {
+			history.modify(|it| *it += 1);
+			now.set(now.get() + Duration::from_millis(500));
+		}
+		// squashed into single step
+		assert_eq!(history.undo_len(), 1);
+		now.set(now.get() + Duration::from_secs(1));
+		for i in 0..5 {
+			history.modify(|it| *it = 10 + i);
+			history.checkpoint();
+		}
+		// oldest steps are dropped
+		assert_eq!(history.undo_iter().map(|it| **it).collect::<Vec<_>>(), vec![11, 12, 13]);
+		assert_eq!(history.undo().map(|it| **it), Some(13));
+		let mut branch = history.branch();
+		assert_eq!(history.redo().map(|it| **it), Some(14));
+		assert!(history.redo().is_none());
+		assert!(!branch.can_redo());
+		assert_eq!(branch.try_modify(|_| Err(())), Err(()));
+		branch.set(Arc::new(100));
+		assert_eq!(branch.undo_iter().map(|it| **it).collect::<Vec<_>>(), vec![11, 12, 13]);
+		assert_eq!(**history.get(), 14);
+		while history.undo().is_some() {}
+		assert_eq!(**history.get(), 11);
+	}
+
+	#[cfg(feature = "mini-bmp")]
+	#[test]
+	fn test_bitmap_history() {
+		use crate::mini_bmp::{BitMap, BGRA};
+
+		let mut image = History::new(BitMap::new(4, 4), 10);
+		image.modify(|it| it.pixels_mut()[0] = BGRA { b: 255, g: 0, r: 0, a: 255 });
+		assert_eq!(image.get().pixels()[0].b, 255);
+		image.undo();
+		assert_eq!(image.get().pixels()[0], BitMap::new(4, 4).pixels()[0]);
+	}
+}
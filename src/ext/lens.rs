@@ -0,0 +1,61 @@
+/// Create closure that borrow nested field mutably; used by `modify_at` that is generated by `#[derive(Modify)]`
+/// `lens!(a.b.c)` expand to `|it| &mut it.a.b.c`
+/// # Example
+/// ```rust
+/// use pedestal_rs::ext::CloneExt;
+/// use pedestal_rs::lens;
+/// fn modify_at<S: Clone, T>(base: &S, lens: impl FnOnce(&mut S) -> &mut T, f: impl FnOnce(&mut T)) -> S {
+///     base.as_owned(|it| f(lens(it)))
+/// }
+/// let base = ((1, 2), 3);
+/// assert_eq!(modify_at(&base, lens!(0.1), |it| *it = 5), ((1, 5), 3));
+/// ```
+/// `#[derive(Modify)]` reject field named `at` because `modify_at` is reserved for lens
+/// ```compile_fail
+/// use pedestal_rs::ext::Modify;
+/// #[derive(Clone, Modify)]
+/// struct Cursor { at: u32 }
+/// ```
+#[macro_export]
+macro_rules! lens {
+	($($path:tt)+) => {
+		|it| &mut it.$($path)+
+	};
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod test {
+	use std::sync::Arc;
+
+	use crate::ext::Modify;
+
+	#[derive(Clone, Debug, PartialEq, Default, Modify)]
+	struct Server {
+		host: String,
+		port: u16,
+	}
+
+	#[derive(Clone, Debug, PartialEq, Default, Modify)]
+	struct Config<T: Clone + 'static> {
+		server: Server,
+		extra: Vec<T>,
+		#[modify(skip)]
+		_version: u32,
+	}
+
+	#[test]
+	fn test_modify() {
+		let base = Config::<u8>::default();
+		let config = base.with_extra(vec![1]).modify_server(|it| it.port = 80).modify_at(lens!(server.host), |it| it.push_str("localhost"));
+		assert_eq!(config.server, Server { host: "localhost".to_string(), port: 80 });
+		assert_eq!(config.extra, vec![1]);
+		assert_eq!(base, Config::default());
+
+		let shared = Arc::new(config);
+		// generated trait return new Arc instead of clone
+		let updated: Arc<Config<u8>> = shared.modify_at(lens!(server.port), |it| *it += 1).with_extra(Vec::new());
+		assert_eq!(updated.server.port, 81);
+		assert_eq!(shared.server.port, 80);
+		assert_eq!(Arc::new(Server::default()).with_port(1).port, 1);
+	}
+}
@@ -1,5 +1,33 @@
+#[cfg(all(feature = "mutation", feature = "async"))]
+pub use async_state::AsyncState;
+#[cfg(feature = "mutation")]
+pub use atomic_arc::AtomicArc;
+#[cfg(feature = "mutation")]
+pub use diff::{Diff, MapChange, Replace, VecChange};
+#[cfg(feature = "derive")]
+pub use pedestal_rs_derive::{Diff, Modify};
+#[cfg(all(feature = "mutation", feature = "collection"))]
+pub use history::History;
 #[cfg(feature = "mutation")]
 pub use mutation_ext::{ArcExt, CloneExt};
+#[cfg(feature = "mutation")]
+pub use observable::{Changed, Closed, Observable, Watcher};
+#[cfg(feature = "mutation")]
+pub use transaction::Transaction;
 
+#[cfg(all(feature = "mutation", feature = "async"))]
+mod async_state;
+#[cfg(feature = "mutation")]
+mod atomic_arc;
+#[cfg(feature = "mutation")]
+mod diff;
+#[cfg(all(feature = "mutation", feature = "collection"))]
+mod history;
+#[cfg(feature = "mutation")]
+mod lens;
+#[cfg(feature = "mutation")]
+mod mutation_ext;
+#[cfg(feature = "mutation")]
+mod observable;
 #[cfg(feature = "mutation")]
-mod mutation_ext;
\ No newline at end of file
+mod transaction;
\ No newline at end of file
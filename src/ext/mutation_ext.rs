@@ -1,7 +1,11 @@
+#[cfg(feature = "async")]
 use std::future::Future;
+#[cfg(feature = "async")]
 use std::pin::Pin;
 use std::sync::Arc;
 
+use crate::ext::{Diff, Transaction};
+
 pub trait CloneExt<T> {
 	/// Clone current variable and accept closure to modify its value
 	/// # Example
@@ -30,7 +34,8 @@ pub trait ArcExt<T: 'static> {
 	fn as_owned_arc<F: FnOnce(&mut T)>(&self, f: F) -> Arc<T>;
 
 	/// Modify Arc's content by replace old value with new modified value  
-	/// this method will return old `Arc<T>`
+	/// this method will return old `Arc<T>`  
+	/// `T` is cloned on every call; use `collection::PersistentVec` instead of large `Vec` to make it cheap
 	/// # Example
 	/// ```rust
 	/// use std::sync::Arc;
@@ -43,6 +48,51 @@ pub trait ArcExt<T: 'static> {
 	/// ```
 	fn modify<F: FnOnce(&mut T)>(&mut self, f: F) -> Arc<T>;
 
+	/// Same as [ArcExt::modify] but `f` can fail; this `Arc<T>` is untouched when `f` return error
+	/// # Example
+	/// ```rust
+	/// use std::sync::Arc;
+	/// use pedestal_rs::ext::ArcExt;
+	/// let mut port = Arc::new(8080u16);
+	/// let res = port.try_modify(|it| {
+	///     *it += 1;
+	///     if *it % 2 == 0 { Ok(()) } else { Err("port must be even") }
+	/// });
+	/// assert_eq!(res, Err("port must be even"));
+	/// assert_eq!(*port, 8080);
+	/// assert_eq!(*port.try_modify(|it| { *it += 2; Ok::<_, ()>(()) }).unwrap(), 8080);
+	/// assert_eq!(*port, 8082);
+	/// ```
+	fn try_modify<E, F: FnOnce(&mut T) -> Result<(), E>>(&mut self, f: F) -> Result<Arc<T>, E>;
+
+	/// Same as [ArcExt::modify] but also return what was changed (see [Diff])
+	/// # Example
+	/// ```rust
+	/// use std::sync::Arc;
+	/// use pedestal_rs::ext::{ArcExt, VecChange};
+	/// let mut users = Arc::new(vec!["alice", "bob"]);
+	/// let (old, change) = users.modify_diff(|it| it.retain(|it| *it != "alice"));
+	/// assert_eq!(*old, vec!["alice", "bob"]);
+	/// assert_eq!(change, Some(vec![VecChange::Remove { index: 0, value: "alice" }]));
+	/// ```
+	fn modify_diff<F: FnOnce(&mut T)>(&mut self, f: F) -> (Arc<T>, Option<T::Change>) where T: Diff;
+
+	/// Stage several modifications and apply them at once with [Transaction::commit]  
+	/// `T` is cloned once on first modification; dropping transaction discard staged value
+	/// # Example
+	/// ```rust
+	/// use std::sync::Arc;
+	/// use pedestal_rs::ext::ArcExt;
+	/// let mut range = Arc::new((0, 10));
+	/// let mut tx = range.transaction();
+	/// tx.modify(|it| it.0 = 20);
+	/// tx.modify(|it| it.1 = 30);
+	/// let (old, new) = tx.try_commit(|it| if it.0 <= it.1 { Ok(()) } else { Err("invalid range") }).unwrap();
+	/// assert_eq!((*old, *new), ((0, 10), (20, 30)));
+	/// assert!(Arc::ptr_eq(&new, &range));
+	/// ```
+	fn transaction(&mut self) -> Transaction<'_, T>;
+
 	/// # Example
 	/// ```rust
 	/// use std::sync::Arc;
@@ -67,6 +117,26 @@ pub trait ArcExt<T: 'static> {
 		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=()> + 'b>>,
 		      F: 'a;
 
+	/// Same as [ArcExt::modify_async] but `f` can fail; this `Arc<T>` is untouched when `f` return error
+	/// # Example
+	/// ```rust
+	/// use std::sync::Arc;
+	/// use futures::executor::block_on;
+	/// use pedestal_rs::ext::ArcExt;
+	/// let mut base = Arc::new("Hello".to_string());
+	/// let res = block_on(base.try_modify_async(|it| Box::pin(async {
+	///     it.clear();
+	///     Err("empty")
+	/// })));
+	/// assert_eq!(res, Err("empty"));
+	/// assert_eq!(*base, "Hello");
+	/// ```
+	#[cfg(feature = "async")]
+	fn try_modify_async<'a, E, F>(&'a mut self, f: F) -> Pin<Box<dyn Future<Output=Result<Arc<T>, E>> + 'a>>
+		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=Result<(), E>> + 'b>>,
+		      F: 'a,
+		      E: 'a;
+
 	/// This implementation block on &mut reference to make it Send+Sync
 	/// # Example
 	/// ```rust
@@ -120,6 +190,23 @@ impl<T: Clone + 'static> ArcExt<T> for Arc<T> {
 		old
 	}
 
+	fn try_modify<E, F: FnOnce(&mut T) -> Result<(), E>>(&mut self, f: F) -> Result<Arc<T>, E> {
+		let mut new = T::clone(self);
+		f(&mut new)?;
+		Ok(std::mem::replace(self, Arc::new(new)))
+	}
+
+	fn modify_diff<F: FnOnce(&mut T)>(&mut self, f: F) -> (Arc<T>, Option<T::Change>) where T: Diff {
+		let old = self.modify(f);
+		let change = old.diff(self);
+		(old, change)
+	}
+
+	#[inline]
+	fn transaction(&mut self) -> Transaction<'_, T> {
+		Transaction::new(self)
+	}
+
 	#[cfg(feature = "async")]
 	fn modify_async<'a, F>(&'a mut self, f: F) -> Pin<Box<dyn Future<Output=Arc<T>> + 'a>>
 		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=()> + 'b>>,
@@ -133,6 +220,19 @@ impl<T: Clone + 'static> ArcExt<T> for Arc<T> {
 		})
 	}
 
+	#[cfg(feature = "async")]
+	fn try_modify_async<'a, E, F>(&'a mut self, f: F) -> Pin<Box<dyn Future<Output=Result<Arc<T>, E>> + 'a>>
+		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=Result<(), E>> + 'b>>,
+		      F: 'a,
+		      E: 'a {
+		let mut new = T::clone(self);
+		Box::pin(async {
+			f(&mut new).await?;
+			Ok(std::mem::replace(self, Arc::new(new)))
+		})
+	}
+
+	#[cfg(feature = "async")]
 	fn modify_async_send<'a, F>(&'a mut self, f: F) -> Pin<Box<dyn Future<Output=Arc<T>> + Send + Sync + 'a>>
 		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=()> + Send + Sync + 'b>> + Send + Sync,
 		      F: 'a,
@@ -0,0 +1,321 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::task::{Context, Poll, Waker};
+
+use crate::ext::ArcExt;
+
+/// Error returned from [Watcher::changed] when [Observable] was dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Closed;
+
+type Callback<T> = Box<dyn FnMut(&Arc<T>) + Send>;
+
+struct Inner<T> {
+	value: Arc<T>,
+	version: u64,
+	closed: bool,
+	wakers: Vec<Waker>,
+}
+
+struct Shared<T> {
+	inner: Mutex<Inner<T>>,
+	/// serialize modifications; `inner` is not locked while new value is computed
+	writer: Mutex<()>,
+	callbacks: Mutex<Vec<(u64, Callback<T>)>>,
+	next_id: AtomicU64,
+}
+
+impl<T> Shared<T> {
+	#[inline]
+	fn lock(&self) -> MutexGuard<'_, Inner<T>> {
+		self.inner.lock().unwrap_or_else(|it| it.into_inner())
+	}
+}
+
+/// `Arc<T>` that publish every new value to subscribers
+/// + callbacks registered by [Observable::on_change] are called synchronously after modification
+/// + [Watcher] created by [Observable::subscribe] can await next value (only latest value is kept)
+/// # Example
+/// ```rust
+/// use futures::executor::block_on;
+/// use pedestal_rs::ext::Observable;
+/// let config = Observable::new(vec![1]);
+/// let mut watcher = config.subscribe();
+/// config.modify(|it| it.push(2));
+/// config.modify(|it| it.push(3));
+/// // intermediate value was skipped
+/// assert_eq!(*block_on(watcher.changed()).unwrap(), vec![1, 2, 3]);
+/// drop(config);
+/// assert!(block_on(watcher.changed()).is_err());
+/// ```
+pub struct Observable<T> {
+	shared: Arc<Shared<T>>,
+}
+
+impl<T> Observable<T> {
+	/// Create new observable value
+	pub fn new(value: T) -> Self {
+		Self::from_arc(Arc::new(value))
+	}
+
+	/// Create new observable value from existing `Arc`
+	pub fn from_arc(value: Arc<T>) -> Self {
+		Self {
+			shared: Arc::new(Shared {
+				inner: Mutex::new(Inner { value, version: 0, closed: false, wakers: Vec::new() }),
+				writer: Mutex::new(()),
+				callbacks: Mutex::new(Vec::new()),
+				next_id: AtomicU64::new(0),
+			})
+		}
+	}
+
+	/// Get current value
+	pub fn get(&self) -> Arc<T> {
+		Arc::clone(&self.shared.lock().value)
+	}
+
+	/// Get number of modifications
+	pub fn version(&self) -> u64 {
+		self.shared.lock().version
+	}
+
+	/// Replace current value and return old value
+	pub fn set(&self, value: T) -> Arc<T> {
+		self.update(|_| Arc::new(value))
+	}
+
+	/// Modify content by replacing old value with modified clone (same as [ArcExt::modify]); return old value  
+	/// `f` can read this observable (eg. [Observable::get], [Observable::subscribe]) but must not modify it
+	pub fn modify(&self, f: impl FnOnce(&mut T)) -> Arc<T> where T: Clone + 'static {
+		self.update(|it| it.as_owned_arc(f))
+	}
+
+	/// Same as [ArcExt::modify_async]; value is not locked while `f` is running,
+	/// so modification that finished in the meantime will be overwritten
+	/// # Example
+	/// ```rust
+	/// use futures::executor::block_on;
+	/// use pedestal_rs::ext::Observable;
+	/// let config = Observable::new("Hello".to_string());
+	/// let old = block_on(config.modify_async(|it| Box::pin(async {
+	///     it.push_str(" world");
+	/// })));
+	/// assert_eq!(*old, "Hello");
+	/// assert_eq!(*config.get(), "Hello world");
+	/// ```
+	#[cfg(feature = "async")]
+	pub async fn modify_async<F>(&self, f: F) -> Arc<T>
+		where for<'b> F: FnOnce(&'b mut T) -> Pin<Box<dyn Future<Output=()> + 'b>>,
+		      T: Clone {
+		let mut new = T::clone(&self.get());
+		f(&mut new).await;
+		self.update(|_| Arc::new(new))
+	}
+
+	/// replace value with result of `f(current)` and notify subscribers
+	fn update(&self, f: impl FnOnce(&Arc<T>) -> Arc<T>) -> Arc<T> {
+		let _writer = self.shared.writer.lock().unwrap_or_else(|it| it.into_inner());
+		let current = Arc::clone(&self.shared.lock().value);
+		let new = f(&current);
+		let mut inner = self.shared.lock();
+		let old = std::mem::replace(&mut inner.value, Arc::clone(&new));
+		inner.version += 1;
+		let wakers = std::mem::take(&mut inner.wakers);
+		// lock callbacks before releasing value so callbacks see values in order
+		let mut callbacks = self.shared.callbacks.lock().unwrap_or_else(|it| it.into_inner());
+		drop(inner);
+		for waker in wakers { waker.wake(); }
+		for (_, callback) in callbacks.iter_mut() { callback(&new); }
+		old
+	}
+
+	/// Create watcher that will be notified when value is changed after this call
+	pub fn subscribe(&self) -> Watcher<T> {
+		let inner = self.shared.lock();
+		Watcher {
+			shared: Arc::clone(&self.shared),
+			version: inner.version,
+			snapshot: Arc::clone(&inner.value),
+		}
+	}
+
+	/// Call `f` with new value after every modification; return id for [Observable::remove_callback]
+	/// `f` must not modify this observable
+	pub fn on_change(&self, f: impl FnMut(&Arc<T>) + Send + 'static) -> u64 {
+		let id = self.shared.next_id.fetch_add(1, Ordering::Relaxed);
+		self.shared.callbacks.lock().unwrap_or_else(|it| it.into_inner()).push((id, Box::new(f)));
+		id
+	}
+
+	/// Same as [Observable::on_change] but `f` is called only when `project(value)` is changed
+	/// # Example
+	/// ```rust
+	/// use std::sync::{Arc, Mutex};
+	/// use pedestal_rs::ext::Observable;
+	/// let config = Observable::new((8080, "info"));
+	/// let restarts = Arc::new(Mutex::new(0));
+	/// let counter = Arc::clone(&restarts);
+	/// config.on_change_by(|it| it.0, move |_| *counter.lock().unwrap() += 1);
+	/// config.modify(|it| it.1 = "debug");
+	/// config.modify(|it| it.0 = 8081);
+	/// assert_eq!(*restarts.lock().unwrap(), 1);
+	/// ```
+	pub fn on_change_by<K>(&self, project: impl Fn(&T) -> K + Send + 'static, mut f: impl FnMut(&Arc<T>) + Send + 'static) -> u64
+		where K: PartialEq + Send + 'static {
+		let mut last = project(&self.get());
+		self.on_change(move |it| {
+			let key = project(it);
+			if key != last {
+				last = key;
+				f(it);
+			}
+		})
+	}
+
+	/// Remove callback that was registered by [Observable::on_change]
+	pub fn remove_callback(&self, id: u64) -> bool {
+		let mut callbacks = self.shared.callbacks.lock().unwrap_or_else(|it| it.into_inner());
+		let len = callbacks.len();
+		callbacks.retain(|(it, _)| *it != id);
+		callbacks.len() != len
+	}
+}
+
+impl<T> Drop for Observable<T> {
+	fn drop(&mut self) {
+		let mut inner = self.shared.lock();
+		inner.closed = true;
+		for waker in inner.wakers.drain(..) { waker.wake(); }
+	}
+}
+
+/// Receiver of [Observable] values; only latest value is kept (watch channel semantics)
+pub struct Watcher<T> {
+	shared: Arc<Shared<T>>,
+	/// version of `snapshot`
+	version: u64,
+	snapshot: Arc<T>,
+}
+
+impl<T> Clone for Watcher<T> {
+	fn clone(&self) -> Self {
+		Self {
+			shared: Arc::clone(&self.shared),
+			version: self.version,
+			snapshot: Arc::clone(&self.snapshot),
+		}
+	}
+}
+
+impl<T> Watcher<T> {
+	/// Get current value without marking it as seen
+	pub fn get(&self) -> Arc<T> {
+		Arc::clone(&self.shared.lock().value)
+	}
+
+	/// Get latest value that was marked as seen
+	pub fn snapshot(&self) -> &Arc<T> {
+		&self.snapshot
+	}
+
+	/// Get current value and mark it as seen
+	pub fn get_and_update(&mut self) -> Arc<T> {
+		let inner = self.shared.lock();
+		self.version = inner.version;
+		self.snapshot = Arc::clone(&inner.value);
+		Arc::clone(&self.snapshot)
+	}
+
+	/// Check if value was changed since last seen value
+	pub fn has_changed(&self) -> bool {
+		self.shared.lock().version != self.version
+	}
+
+	/// Wait until value is changed, mark it as seen and return it
+	/// return `Err(Closed)` if [Observable] was dropped
+	pub fn changed(&mut self) -> Changed<'_, T> {
+		Changed(self)
+	}
+
+	/// Wait until `project(value)` is changed, mark it as seen and return it
+	pub async fn changed_by<K: PartialEq>(&mut self, project: impl Fn(&T) -> K) -> Result<Arc<T>, Closed> {
+		let last = project(&self.snapshot);
+		loop {
+			let value = self.changed().await?;
+			if project(&value) != last {
+				return Ok(value);
+			}
+		}
+	}
+}
+
+/// Future returned from [Watcher::changed]
+pub struct Changed<'a, T>(&'a mut Watcher<T>);
+
+impl<'a, T> Future for Changed<'a, T> {
+	type Output = Result<Arc<T>, Closed>;
+
+	fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+		let watcher = &mut *self.0;
+		let mut inner = watcher.shared.lock();
+		if inner.version != watcher.version {
+			watcher.version = inner.version;
+			watcher.snapshot = Arc::clone(&inner.value);
+			return Poll::Ready(Ok(Arc::clone(&watcher.snapshot)));
+		}
+		if inner.closed {
+			return Poll::Ready(Err(Closed));
+		}
+		if !inner.wakers.iter().any(|it| it.will_wake(cx.waker())) {
+			inner.wakers.push(cx.waker().clone());
+		}
+		Poll::Pending
+	}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::{Arc, Mutex};
+	use std::thread;
+
+	use futures::executor::block_on;
+
+	use crate::ext::Observable;
+
+	#[test]
+	fn test_observable() {
+		let value = Observable::new((0, 0));
+		let log = Arc::new(Mutex::new(Vec::new()));
+		let id = {
+			let log = Arc::clone(&log);
+			value.on_change(move |it| log.lock().unwrap().push(it.0))
+		};
+		let mut watcher = value.subscribe();
+		let mut by_second = value.subscribe();
+		let handle = thread::spawn(move || block_on(by_second.changed_by(|it| it.1)).unwrap());
+		for i in 1..=10 {
+			value.modify(|it| it.0 = i);
+		}
+		assert!(watcher.has_changed());
+		assert_eq!(block_on(watcher.changed()).unwrap().0, 10);
+		assert!(!watcher.has_changed());
+		value.modify(|it| it.1 = 1);
+		assert_eq!(*handle.join().unwrap(), (10, 1));
+		assert!(value.remove_callback(id));
+		value.set((0, 0));
+		assert_eq!(*log.lock().unwrap(), (1..=10).chain([10]).collect::<Vec<_>>());
+		assert_eq!(value.version(), 12);
+
+		// reading inside modification doesn't deadlock
+		let mut watcher = value.subscribe();
+		value.modify(|it| {
+			assert_eq!(value.version(), 12);
+			assert_eq!(*value.get(), *it);
+			it.0 = 1;
+		});
+		assert_eq!(block_on(watcher.changed()).unwrap().0, 1);
+	}
+}
@@ -0,0 +1,115 @@
+use std::sync::Arc;
+
+/// Staged modifications of `Arc<T>`; created by [crate::ext::ArcExt::transaction]
+/// + nothing is visible through the `Arc` until [Transaction::commit]
+/// + dropping transaction without commit discard every staged modification
+/// # Example
+/// ```rust
+/// use std::sync::Arc;
+/// use pedestal_rs::ext::ArcExt;
+/// let mut users = Arc::new(vec!["alice"]);
+/// let mut tx = users.transaction();
+/// tx.modify(|it| it.push("bob"));
+/// // failed modification leave staged value untouched
+/// assert!(tx.try_modify(|it| { it.push("bob"); Err("duplicated") }).is_err());
+/// assert_eq!(*tx.get(), vec!["alice", "bob"]);
+/// tx.commit();
+/// assert_eq!(*users, vec!["alice", "bob"]);
+/// ```
+pub struct Transaction<'a, T> {
+	target: &'a mut Arc<T>,
+	/// None until first modification
+	staged: Option<T>,
+}
+
+impl<'a, T: Clone> Transaction<'a, T> {
+	pub(crate) fn new(target: &'a mut Arc<T>) -> Self {
+		Self { target, staged: None }
+	}
+
+	/// Get staged value (or current value if nothing was staged)
+	pub fn get(&self) -> &T {
+		self.staged.as_ref().unwrap_or(self.target)
+	}
+
+	/// Check if any modification was staged
+	pub fn is_modified(&self) -> bool {
+		self.staged.is_some()
+	}
+
+	fn staged(&mut self) -> &mut T {
+		let target = &*self.target;
+		self.staged.get_or_insert_with(|| T::clone(target))
+	}
+
+	/// Stage modification
+	pub fn modify(&mut self, f: impl FnOnce(&mut T)) -> &mut Self {
+		f(self.staged());
+		self
+	}
+
+	/// Stage fallible modification; staged value is untouched when `f` return error
+	pub fn try_modify<E>(&mut self, f: impl FnOnce(&mut T) -> Result<(), E>) -> Result<&mut Self, E> {
+		let mut value = T::clone(self.get());
+		f(&mut value)?;
+		self.staged = Some(value);
+		Ok(self)
+	}
+
+	/// Apply staged value and return `(old, new)` snapshots
+	/// return `(current, current)` if nothing was staged
+	pub fn commit(self) -> (Arc<T>, Arc<T>) {
+		match self.staged {
+			Some(staged) => {
+				let new = Arc::new(staged);
+				let old = std::mem::replace(self.target, Arc::clone(&new));
+				(old, new)
+			}
+			None => (Arc::clone(self.target), Arc::clone(self.target)),
+		}
+	}
+
+	/// Validate staged value then commit; nothing is applied when `validate` return error
+	pub fn try_commit<E>(self, validate: impl FnOnce(&T) -> Result<(), E>) -> Result<(Arc<T>, Arc<T>), E> {
+		validate(self.get())?;
+		Ok(self.commit())
+	}
+
+	/// Discard staged value
+	pub fn rollback(self) {}
+}
+
+#[cfg(test)]
+mod test {
+	use std::sync::Arc;
+
+	use crate::ext::ArcExt;
+
+	#[test]
+	fn test_transaction() {
+		let mut value = Arc::new(vec![1, 2]);
+		let snapshot = Arc::clone(&value);
+		let (old, new) = value.transaction().commit();
+		assert!(Arc::ptr_eq(&old, &new));
+		let mut tx = value.transaction();
+		tx.modify(|it| it.push(3)).modify(|it| it.push(4));
+		assert_eq!(tx.get(), &vec![1, 2, 3, 4]);
+		assert_eq!(tx.try_commit(|it| if it.len() > 3 { Err(it.len()) } else { Ok(()) }), Err(4));
+		assert!(Arc::ptr_eq(&value, &snapshot));
+		let mut tx = value.transaction();
+		tx.try_modify(|it| { it.retain(|it| *it > 1); Ok::<_, ()>(()) }).unwrap().modify(|it| it.push(5));
+		let (old, new) = tx.commit();
+		assert!(Arc::ptr_eq(&old, &snapshot));
+		assert_eq!(*value, vec![2, 5]);
+		let mut tx = value.transaction();
+		assert_eq!(tx.try_modify(|it| { it.clear(); Err("fail") }).err(), Some("fail"));
+		assert!(!tx.is_modified());
+		tx.modify(|it| it.push(6));
+		assert!(tx.try_modify(|it| { it.clear(); Err(()) }).is_err());
+		assert_eq!(tx.get(), &vec![2, 5, 6]);
+		tx.rollback();
+		assert!(Arc::ptr_eq(&new, &value));
+		assert_eq!(value.try_modify(|_| Err("fail")), Err("fail"));
+		assert!(Arc::ptr_eq(&new, &value));
+	}
+}
@@ -1,3 +1,13 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+// `new_vec!` and `try_new_vec!` refer to `Vec` through this path so they work in `no_std` crates
+#[cfg(feature = "alloc")]
+#[doc(hidden)]
+pub use alloc::vec::Vec as __Vec;
+// generated code of derive macros refer to `::pedestal_rs`
+#[cfg(feature = "derive")]
+extern crate self as pedestal_rs;
 #[deny(missing_docs)]
 /// helper related to file system
 #[cfg(feature = "fs")]
@@ -1,7 +1,7 @@
-use std::alloc::Layout;
 use std::ops::Deref;
 use std::slice;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 #[repr(C, align(2))]
 pub struct BGRA {
 	pub b: u8,
@@ -14,30 +14,34 @@ pub struct BGRA {
 /// # Limitation
 /// + BGRA 32bpp format only
 /// + Can't read from anything that is not RGBA 32bpp
+#[derive(Clone)]
 pub struct BitMap {
-	data: Vec<u8>,
+	/// bytes of bitmap file; stored as u16 so pixels (at offset 54) are aligned for [BGRA]
+	data: Vec<u16>,
+	/// number of bytes
+	len: usize,
 }
 
 macro_rules! raw_access {
     ($r_name:ident, $w_name:ident, $bytes:literal, $var:ident) => {
 		#[allow(unused)]
 		fn $r_name(&self, offset: usize) -> Option<$var> {
-			if offset >= self.data.len() { return None; }
+			if offset >= self.len { return None; }
 			let mut buf = [0; $bytes];
-			buf.copy_from_slice(&self.data[offset..offset + $bytes]);
+			buf.copy_from_slice(&self.bytes()[offset..offset + $bytes]);
 			Some($var::from_le_bytes(buf))
 		}
 	
 		#[allow(unused)]
 		fn $w_name(&mut self, offset: usize, value: $var) {
-			if offset >= self.data.len() { return; }
+			if offset >= self.len { return; }
 			let buf: [u8; $bytes] = value.to_le_bytes();
-			self.data[offset..offset + $bytes].copy_from_slice(&buf);
+			self.bytes_mut()[offset..offset + $bytes].copy_from_slice(&buf);
 		}
     };
 }
 
-static BMP_HEADER: &[u8] = &[b'B', b'M'];
+static BMP_HEADER: &[u8] = b"BM";
 /// u32
 const BM_OFFSET: usize = 2;
 /// u32; off + 4 reserve bytes
@@ -92,7 +96,26 @@ impl BitMap {
 	/// # Safety
 	/// if input vec is output of this [BitMap::deref], this function is safe
 	pub unsafe fn from_vec(data: Vec<u8>) -> Self {
-		Self { data }
+		Self::copy_from(&data)
+	}
+
+	/// zero filled bitmap of `len` bytes
+	fn zeroed(len: usize) -> Self {
+		Self { data: vec![0; len.div_ceil(2)], len }
+	}
+
+	fn copy_from(data: &[u8]) -> Self {
+		let mut it = Self::zeroed(data.len());
+		it.bytes_mut().copy_from_slice(data);
+		it
+	}
+
+	fn bytes(&self) -> &[u8] {
+		unsafe { slice::from_raw_parts(self.data.as_ptr().cast::<u8>(), self.len) }
+	}
+
+	fn bytes_mut(&mut self) -> &mut [u8] {
+		unsafe { slice::from_raw_parts_mut(self.data.as_mut_ptr().cast::<u8>(), self.len) }
 	}
 
 
@@ -101,23 +124,23 @@ impl BitMap {
 		if !Self::validate_compatible_header(data) {
 			return None;
 		}
-		let layout = Layout::from_size_align(data.len(), 2).unwrap();
-		let _data = unsafe {
-			let ptr = std::alloc::alloc(layout);
-			ptr.copy_from(data.as_ptr(), data.len());
-			Vec::from_raw_parts(ptr, data.len(), layout.size())
-		};
-		Some(Self {
-			data: _data,
-		})
+		Some(Self::copy_from(data))
 	}
 
 	fn validate_compatible_header(data: &[u8]) -> bool {
+		check!(data.len() >= BM_PIXEL_START);
 		check!(&data[..BM_OFFSET] == b"BM");
 
 		check_by!(data[BM_OFFSET_PIXEL_DATA], BM_PIXEL_START as u32);
 		check_by!(data[BM_BPP], 32u32);
 		check_by!(data[BM_COMPRESSION], 0u16);
+		// pixels must fit in data
+		let read = |offset: usize| u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+		// same as [BitMap::width] and [BitMap::height]
+		let width = read(BM_WIDTH) as usize;
+		let height = (read(BM_HEIGHT) as i32).unsigned_abs() as usize;
+		let size = width.checked_mul(height).and_then(|it| it.checked_mul(4));
+		check!(size.is_some_and(|it| it <= data.len() - BM_PIXEL_START));
 		true
 	}
 
@@ -126,16 +149,11 @@ impl BitMap {
 		assert!(width > 0);
 		assert!(height > 0);
 		let len = 54 + (((width * height) as usize) << 2);
-		let layout = Layout::from_size_align(len, 2).unwrap();
-		let data = unsafe {
-			let ptr = std::alloc::alloc_zeroed(layout);
-			Vec::from_raw_parts(ptr, len, layout.size())
-		};
-		let mut it = Self { data };
+		let mut it = Self::zeroed(len);
 
 		// copy header
-		it.data[..2].copy_from_slice(BMP_HEADER);
-		it.write_32(BM_OFFSET, it.data.len() as u32);
+		it.bytes_mut()[..2].copy_from_slice(BMP_HEADER);
+		it.write_32(BM_OFFSET, len as u32);
 		it.write_32(BM_OFFSET_PIXEL_DATA, BM_PIXEL_START as u32);
 
 		it.write_32(BM_HEADER_SIZE, 40u32);
@@ -158,7 +176,12 @@ impl BitMap {
 
 	/// Get height of this image
 	pub fn height(&self) -> u32 {
-		self.read_32(BM_WIDTH).unwrap()
+		(self.read_32(BM_HEIGHT).unwrap() as i32).unsigned_abs()
+	}
+
+	/// Check if rows are stored from top to bottom (negative height in header)
+	pub fn is_top_down(&self) -> bool {
+		(self.read_32(BM_HEIGHT).unwrap() as i32) < 0
 	}
 
 	/// Get pixel slice
@@ -171,13 +194,13 @@ impl BitMap {
 	/// Get mutable pixel slice
 	pub fn pixels_mut(&mut self) -> &mut [BGRA] {
 		unsafe {
-			slice::from_raw_parts_mut(self.data.as_mut_ptr().add(BM_PIXEL_START).cast::<BGRA>(), (self.width() * self.height()) as _)
+			let len = (self.width() * self.height()) as _;
+			slice::from_raw_parts_mut(self.bytes_mut().as_mut_ptr().add(BM_PIXEL_START).cast::<BGRA>(), len)
 		}
 	}
 
 	pub fn pixel_bytes(&self) -> &[u8] {
-		let raw = &self.data;
-		unsafe { slice::from_raw_parts(raw.as_ptr().add(BM_PIXEL_START).cast::<u8>(), raw.len() - 54) }
+		&self.bytes()[BM_PIXEL_START..]
 	}
 }
 
@@ -185,7 +208,7 @@ impl Deref for BitMap {
 	type Target = [u8];
 
 	fn deref(&self) -> &Self::Target {
-		&self.data
+		self.bytes()
 	}
 }
 
@@ -197,5 +220,10 @@ mod test {
 	fn test_bmp() {
 		let bmp = BitMap::new(4, 4);
 		std::fs::write("test.bmp", &*bmp).unwrap();
+		assert!(BitMap::from_raw(&bmp[..60]).is_none());
+		assert!(BitMap::from_raw(&bmp[..10]).is_none());
+		let clone = bmp.clone();
+		assert_eq!(clone.as_ptr() as usize % 2, 0);
+		assert_eq!(*clone, *bmp);
 	}
 }
\ No newline at end of file